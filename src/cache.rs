@@ -0,0 +1,204 @@
+use std::{
+    collections::BTreeMap,
+    fs::{self, File},
+    io::{BufReader, BufWriter, ErrorKind},
+    path::{Path, PathBuf},
+};
+
+use git_moves_together::{Change, ChangedFile, Commit, Delta};
+
+use crate::cli_errors::Error;
+
+const DIRECTORY: &str = "git-moves-together";
+/// How many characters of a commit's hash pick the file its diff is kept in
+const PREFIX: usize = 2;
+
+/// The files each commit changed, saved inside the repository's git
+/// directory so later runs only need to diff commits they haven't seen.
+/// Each combination of diff options gets its own directory, as they change
+/// what a diff finds, and the commits in it are split into files by the
+/// start of their hash, so a run only reads the files for the commits it
+/// looks at and only rewrites the ones it added commits to.
+pub struct Cache {
+    directory: PathBuf,
+    shards: BTreeMap<String, Shard>,
+}
+
+/// The commits whose hashes start the same way
+#[derive(Default)]
+struct Shard {
+    deltas: BTreeMap<String, Vec<(ChangedFile, Change)>>,
+    changed: bool,
+}
+
+impl Cache {
+    /// Nothing is read until a commit is looked up
+    pub(crate) fn load(git_dir: &Path, diff_options: &str) -> Self {
+        Self {
+            directory: git_dir
+                .join(DIRECTORY)
+                .join(format!("deltas-v2-{diff_options}")),
+            shards: BTreeMap::new(),
+        }
+    }
+
+    pub(crate) fn get(&mut self, commit: &Commit) -> Option<Delta> {
+        let hash = String::from(commit.hash().clone());
+        self.shard(&hash)
+            .deltas
+            .get(&hash)
+            .map(|changes| Delta::for_commit(commit, changes.clone()))
+    }
+
+    pub(crate) fn insert(&mut self, delta: &Delta) {
+        let hash = String::from(delta.hash().clone());
+        let shard = self.shard(&hash);
+        shard.deltas.insert(
+            hash,
+            delta
+                .changes()
+                .iter()
+                .map(|(file, change)| (file.clone(), *change))
+                .collect(),
+        );
+        shard.changed = true;
+    }
+
+    pub(crate) fn save(&self) -> Result<(), Error> {
+        for (prefix, shard) in &self.shards {
+            if !shard.changed {
+                continue;
+            }
+            fs::create_dir_all(&self.directory)?;
+
+            // Write next to the file and move it into place, so a run that
+            // is interrupted or racing another never leaves half a file behind
+            let path = self.path(prefix);
+            let partial = path.with_extension(format!("{}.partial", std::process::id()));
+            serde_json::to_writer(BufWriter::new(File::create(&partial)?), &shard.deltas)?;
+            fs::rename(&partial, &path)?;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn clear(git_dir: &Path) -> Result<(), Error> {
+        match fs::remove_dir_all(git_dir.join(DIRECTORY)) {
+            Err(error) if error.kind() != ErrorKind::NotFound => Err(error.into()),
+            _ => Ok(()),
+        }
+    }
+
+    /// A missing or unreadable file is treated as empty and replaced on save
+    fn shard(&mut self, hash: &str) -> &mut Shard {
+        let prefix = hash.get(..PREFIX).unwrap_or(hash);
+        if !self.shards.contains_key(prefix) {
+            let deltas = File::open(self.path(prefix))
+                .ok()
+                .and_then(|file| serde_json::from_reader(BufReader::new(file)).ok())
+                .unwrap_or_default();
+            self.shards.insert(
+                prefix.to_string(),
+                Shard {
+                    deltas,
+                    changed: false,
+                },
+            );
+        }
+        self.shards
+            .get_mut(prefix)
+            .expect("The shard was just loaded")
+    }
+
+    fn path(&self, prefix: &str) -> PathBuf {
+        self.directory.join(format!("{prefix}.json"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use tempfile::tempdir;
+    use time::OffsetDateTime;
+
+    use git_moves_together::{Author, Change, Commit, Delta, Kind};
+
+    use crate::cache::Cache;
+
+    fn commit(hash: &str) -> Commit {
+        Commit::new(
+            hash.into(),
+            vec![],
+            OffsetDateTime::now_utc(),
+            Author::new("John Doe", "john@doe.org"),
+            "Commit message\n".into(),
+        )
+    }
+
+    #[test]
+    fn cached_deltas_survive_a_round_trip_to_disk() {
+        let dir = tempdir().unwrap();
+        let commit = commit("1");
+        let delta = Delta::for_commit(
+            &commit,
+            vec![
+                ("file_1".into(), Change::new(1, 2).with_kind(Kind::Added)),
+                ("file_2".into(), Change::default().with_binary(true)),
+            ],
+        );
+        let mut cache = Cache::load(dir.path(), "renames");
+        cache.insert(&delta);
+        cache.save().unwrap();
+
+        assert_eq!(Cache::load(dir.path(), "renames").get(&commit), Some(delta));
+    }
+
+    #[test]
+    fn each_set_of_diff_options_has_its_own_cache() {
+        let dir = tempdir().unwrap();
+        let mut cache = Cache::load(dir.path(), "renames");
+        cache.insert(&Delta::for_commit(&commit("1"), vec![]));
+        cache.save().unwrap();
+
+        assert_eq!(
+            Cache::load(dir.path(), "renames-lines").get(&commit("1")),
+            None
+        );
+    }
+
+    #[test]
+    fn clearing_forgets_everything() {
+        let dir = tempdir().unwrap();
+        let mut cache = Cache::load(dir.path(), "renames");
+        cache.insert(&Delta::for_commit(&commit("1"), vec![]));
+        cache.save().unwrap();
+
+        Cache::clear(dir.path()).unwrap();
+        Cache::clear(dir.path()).unwrap();
+
+        assert_eq!(Cache::load(dir.path(), "renames").get(&commit("1")), None);
+    }
+
+    #[test]
+    fn only_the_files_holding_new_commits_are_rewritten() {
+        let dir = tempdir().unwrap();
+        let mut cache = Cache::load(dir.path(), "renames");
+        cache.insert(&Delta::for_commit(&commit("aa1"), vec![]));
+        cache.save().unwrap();
+        let old = dir
+            .path()
+            .join("git-moves-together/deltas-v2-renames/aa.json");
+        fs::write(&old, "untouched").unwrap();
+
+        let new = commit("bb1");
+        let mut cache = Cache::load(dir.path(), "renames");
+        cache.insert(&Delta::for_commit(&new, vec![]));
+        cache.save().unwrap();
+
+        assert_eq!(fs::read_to_string(old).unwrap(), "untouched");
+        assert_eq!(
+            Cache::load(dir.path(), "renames").get(&new),
+            Some(Delta::for_commit(&new, vec![]))
+        );
+    }
+}
@@ -1,8 +1,169 @@
-use clap::Parser;
+use std::{num::NonZeroUsize, path::PathBuf};
+
+use clap::{
+    Parser, Subcommand, ValueEnum,
+    builder::{EnumValueParser, TypedValueParser},
+};
+use git_moves_together::{Kind, Period};
+use regex::Regex;
 
 #[derive(Parser, Debug)]
-#[clap(author, version, about)]
+#[clap(author, version, about, args_conflicts_with_subcommands = true)]
 pub struct Args {
+    #[clap(subcommand)]
+    pub command: Option<Command>,
+    #[clap(flatten)]
+    pub analysis: Analysis,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Save the coupling of the given repositories to a snapshot file
+    Snapshot {
+        /// Where to write the snapshot
+        #[clap(short = 'o', long = "output")]
+        output: PathBuf,
+        #[clap(flatten)]
+        analysis: Analysis,
+    },
+    /// Compare two snapshots to see which pairs appeared, disappeared or moved
+    Diff {
+        /// The earlier snapshot
+        old: PathBuf,
+        /// The later snapshot
+        new: PathBuf,
+        /// Smallest change in together percentage to report a pair as moved
+        #[clap(
+            short = 'm',
+            long = "min-delta",
+            env = "MIN_DELTA",
+            default_value_t = 5.0
+        )]
+        min_delta: f64,
+    },
+    /// Show how the coupling of the strongest pairs changed over time
+    Trend {
+        /// How much history each step of the trend covers
+        #[clap(short = 'p', long = "period", env = "PERIOD", value_parser = PeriodArg::parser(), default_value = "monthly")]
+        period: Period,
+        /// How many of the most strongly coupled pairs to follow
+        #[clap(short = 'n', long = "top", env = "TOP", default_value_t = 10)]
+        top: usize,
+        /// How to print the trend
+        #[clap(short = 'f', long = "format", env = "FORMAT", value_enum, default_value_t = Format::Table)]
+        format: Format,
+        #[clap(flatten)]
+        analysis: Analysis,
+    },
+    /// List the files that change most often and drag others along with them
+    Hotspots {
+        /// Only show this many of the hottest files
+        #[clap(short = 'n', long = "top", env = "TOP")]
+        top: Option<usize>,
+        /// How to print the hotspots
+        #[clap(short = 'f', long = "format", env = "FORMAT", value_enum, default_value_t = Format::Table)]
+        format: Format,
+        #[clap(flatten)]
+        analysis: Analysis,
+    },
+    /// Manage the diffs saved between runs
+    Cache {
+        #[clap(subcommand)]
+        action: CacheAction,
+    },
+    /// List when files were added, deleted or renamed
+    Lifecycle {
+        /// How to print the lifecycle events
+        #[clap(short = 'f', long = "format", env = "FORMAT", value_enum, default_value_t = Format::Table)]
+        format: Format,
+        #[clap(flatten)]
+        analysis: Analysis,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum CacheAction {
+    /// Forget the saved diffs, so the next run diffs every commit again
+    Clear {
+        /// A repository whose cache to clear
+        #[clap(env, default_values = &["."])]
+        git_repo: Vec<String>,
+    },
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Format {
+    Table,
+    Csv,
+    Json,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Backend {
+    #[cfg(feature = "libgit2")]
+    Libgit2,
+    #[cfg(feature = "gix")]
+    Gix,
+}
+
+/// The library's [`Period`] as a command line value
+#[derive(ValueEnum, Debug, Clone, Copy, Eq, PartialEq)]
+enum PeriodArg {
+    /// ISO weeks, starting on Monday
+    Weekly,
+    /// Calendar months
+    Monthly,
+    /// Calendar quarters, starting in January
+    Quarterly,
+}
+
+impl PeriodArg {
+    fn parser() -> impl TypedValueParser<Value = Period> {
+        EnumValueParser::<Self>::new().map(|period| match period {
+            Self::Weekly => Period::Weekly,
+            Self::Monthly => Period::Monthly,
+            Self::Quarterly => Period::Quarterly,
+        })
+    }
+}
+
+/// The library's [`Kind`] as a command line value
+#[derive(ValueEnum, Debug, Clone, Copy, Eq, PartialEq)]
+enum KindArg {
+    /// The file's content or mode changed
+    Modified,
+    /// The file moved, and is known by its new path
+    Renamed,
+    /// The file appeared, including as a copy of another
+    Added,
+    /// The file went away, and is known by its old path
+    Deleted,
+}
+
+impl KindArg {
+    fn parser() -> impl TypedValueParser<Value = Kind> {
+        EnumValueParser::<Self>::new().map(|kind| match kind {
+            Self::Modified => Kind::Modified,
+            Self::Renamed => Kind::Renamed,
+            Self::Added => Kind::Added,
+            Self::Deleted => Kind::Deleted,
+        })
+    }
+}
+
+impl Default for Backend {
+    /// libgit2 unless it was left out of the build
+    fn default() -> Self {
+        #[cfg(feature = "libgit2")]
+        return Self::Libgit2;
+        #[cfg(not(feature = "libgit2"))]
+        return Self::Gix;
+    }
+}
+
+#[derive(clap::Args, Debug)]
+#[allow(clippy::struct_excessive_bools)]
+pub struct Analysis {
     /// A repository to analyse
     #[clap(env, default_values = &["."])]
     pub git_repo: Vec<String>,
@@ -12,13 +173,89 @@ pub struct Args {
     /// Group commits by similar time window rather than by commit id
     #[clap(short = 't', long = "time-window-minutes", env = "TIME_WINDOW_MINUTES", value_parser = clap::value_parser!(i64).range(1..))]
     pub time_window_minutes: Option<i64>,
+    /// Group commits that happen within the given minutes of each other
+    #[clap(short = 's', long = "sliding-window-minutes", env = "SLIDING_WINDOW_MINUTES", value_parser = clap::value_parser!(i64).range(1..), conflicts_with = "time_window_minutes")]
+    pub sliding_window_minutes: Option<i64>,
+    /// Only group commits in the same time window if they have the same author
+    #[clap(
+        short = 'a',
+        long = "per-author",
+        env = "PER_AUTHOR",
+        requires = "time_window_minutes"
+    )]
+    pub per_author: bool,
+    /// Group commits by the ticket this pattern finds in their message, using the first capture group if there is one
+    #[clap(long = "ticket-pattern", env = "TICKET_PATTERN", value_parser = Regex::new, conflicts_with_all = ["time_window_minutes", "sliding_window_minutes"])]
+    pub ticket_pattern: Option<Regex>,
+    /// Group the commits brought in by a merge on the mainline with that merge
+    #[clap(
+        long = "by-pull-request",
+        env = "BY_PULL_REQUEST",
+        conflicts_with_all = ["time_window_minutes", "sliding_window_minutes", "ticket_pattern"]
+    )]
+    pub by_pull_request: bool,
+    /// Ignore commits whose message matches this pattern
+    #[clap(long = "exclude-message", env = "EXCLUDE_MESSAGE", value_parser = Regex::new)]
+    pub exclude_message: Vec<Regex>,
+    /// Only include commits with these conventional commit types
+    #[clap(long = "include-type", env = "INCLUDE_TYPE", value_delimiter = ',')]
+    pub include_type: Vec<String>,
+    /// Ignore commits whose author, as "Name <email>", matches this pattern
+    #[clap(long = "exclude-author", env = "EXCLUDE_AUTHOR", value_parser = Regex::new)]
+    pub exclude_author: Vec<Regex>,
+    /// Leave files out of a commit when they changed in these ways, such as additions that come with boilerplate registration
+    #[clap(
+        long = "exclude-kind",
+        env = "EXCLUDE_KIND",
+        value_parser = KindArg::parser(),
+        value_delimiter = ','
+    )]
+    pub exclude_kind: Vec<Kind>,
+    /// Report a file that moved as renamed, rather than as deleted from its old path and added at its new one
+    #[clap(long = "find-renames", env = "FIND_RENAMES")]
+    pub find_renames: bool,
+    /// Leave out files git considers binary, such as images, fonts and build artefacts
+    #[clap(long = "exclude-binary", env = "EXCLUDE_BINARY")]
+    pub exclude_binary: bool,
+    /// Look inside submodules that are checked out and count the files that changed in them
+    #[clap(long = "recurse-submodules", env = "RECURSE_SUBMODULES")]
+    pub recurse_submodules: bool,
+    /// Leave out files that are no longer in the analysed revision
+    #[clap(long = "only-existing", env = "ONLY_EXISTING")]
+    pub only_existing: bool,
+    /// Set by the lifecycle command, which reports how files changed
+    #[clap(skip)]
+    pub lifecycle: bool,
+    /// How many commits to diff at the same time, defaulting to the number of CPUs
+    #[clap(short = 'j', long = "jobs", env = "JOBS")]
+    pub jobs: Option<NonZeroUsize>,
+    /// Diff every commit again rather than reusing the diffs saved by earlier runs
+    #[clap(long = "no-cache", env = "NO_CACHE")]
+    pub no_cache: bool,
+    /// Count the lines each commit adds and removes, and weigh coupling by them
+    #[clap(long = "churn", env = "CHURN")]
+    pub churn: bool,
+    /// Don't show progress on stderr, which is only shown when it is a terminal
+    #[clap(short = 'q', long = "quiet", env = "QUIET")]
+    pub quiet: bool,
+    /// Which git implementation reads the repository
+    #[clap(long = "backend", env = "BACKEND", value_enum, default_value_t = Backend::default())]
+    pub backend: Backend,
+    /// Read each repository argument as a file of `git log` output in the format shown in the README, or `-` for stdin
+    #[clap(
+        long = "git-log",
+        env = "GIT_LOG",
+        conflicts_with_all = ["backend", "no_cache", "jobs", "recurse_submodules"]
+    )]
+    pub git_log: bool,
 }
 
 #[cfg(test)]
 mod tests {
     use clap::Parser;
+    use git_moves_together::{Kind, Period};
 
-    use super::Args;
+    use super::{Args, CacheAction, Command, Format};
 
     #[test]
     fn rejects_zero_time_window() {
@@ -37,4 +274,131 @@ mod tests {
             "A negative time window is nonsensical and must be rejected"
         );
     }
+
+    #[test]
+    fn rejects_both_kinds_of_time_window() {
+        let result = Args::try_parse_from([
+            "git-moves-together",
+            "--time-window-minutes",
+            "5",
+            "--sliding-window-minutes",
+            "5",
+        ]);
+        assert!(
+            result.is_err(),
+            "Fixed and sliding windows are different groupings and can't be combined"
+        );
+    }
+
+    #[test]
+    fn per_author_needs_a_time_window() {
+        let result = Args::try_parse_from(["git-moves-together", "--per-author"]);
+        assert!(
+            result.is_err(),
+            "Grouping per author only makes sense within a time window"
+        );
+    }
+
+    #[test]
+    fn git_log_rejects_options_for_reading_repositories() {
+        for option in [
+            &["--backend", "libgit2"][..],
+            &["--no-cache"],
+            &["--jobs", "2"],
+            &["--recurse-submodules"],
+        ] {
+            let result = Args::try_parse_from(
+                ["git-moves-together", "--git-log"]
+                    .iter()
+                    .chain(option)
+                    .chain(&["-"]),
+            );
+            assert!(
+                result.is_err(),
+                "A git log is read as it is, so {option:?} would do nothing"
+            );
+        }
+        assert!(Args::try_parse_from(["git-moves-together", "--git-log", "-"]).is_ok());
+    }
+
+    #[test]
+    fn rejects_zero_jobs() {
+        let result = Args::try_parse_from(["git-moves-together", "--jobs", "0"]);
+        assert!(result.is_err(), "Diffing needs at least one worker");
+    }
+
+    #[test]
+    fn rejects_an_invalid_ticket_pattern() {
+        let result = Args::try_parse_from(["git-moves-together", "--ticket-pattern", "("]);
+        assert!(result.is_err(), "An unparseable regex must be rejected");
+    }
+
+    #[test]
+    fn included_types_can_be_given_as_a_list() {
+        let args =
+            Args::try_parse_from(["git-moves-together", "--include-type", "feat,fix"]).unwrap();
+        assert_eq!(args.analysis.include_type, vec!["feat", "fix"]);
+    }
+
+    #[test]
+    fn excluded_kinds_can_be_given_as_a_list() {
+        let args = Args::try_parse_from(["git-moves-together", "--exclude-kind", "added,deleted"])
+            .unwrap();
+        assert_eq!(args.analysis.exclude_kind, vec![Kind::Added, Kind::Deleted]);
+    }
+
+    #[test]
+    fn repositories_without_a_subcommand_are_analysed() {
+        let args = Args::try_parse_from(["git-moves-together", "repo-a", "repo-b"]).unwrap();
+        assert!(args.command.is_none());
+        assert_eq!(args.analysis.git_repo, vec!["repo-a", "repo-b"]);
+    }
+
+    #[test]
+    fn snapshot_takes_its_own_repositories() {
+        let args = Args::try_parse_from([
+            "git-moves-together",
+            "snapshot",
+            "--output",
+            "baseline.json",
+            "repo-a",
+        ])
+        .unwrap();
+        match args.command {
+            Some(Command::Snapshot { output, analysis }) => {
+                assert_eq!(output.to_string_lossy(), "baseline.json");
+                assert_eq!(analysis.git_repo, vec!["repo-a"]);
+            }
+            other => panic!("Expected a snapshot command, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn cache_clear_defaults_to_the_current_repository() {
+        let args = Args::try_parse_from(["git-moves-together", "cache", "clear"]).unwrap();
+        match args.command {
+            Some(Command::Cache {
+                action: CacheAction::Clear { git_repo },
+            }) => assert_eq!(git_repo, vec!["."]),
+            other => panic!("Expected a cache clear command, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn trend_defaults_to_a_monthly_table() {
+        let args = Args::try_parse_from(["git-moves-together", "trend"]).unwrap();
+        match args.command {
+            Some(Command::Trend {
+                period,
+                top,
+                format,
+                ..
+            }) => {
+                assert_eq!(period, Period::Monthly);
+                assert_eq!(top, 10);
+                assert_eq!(format, Format::Table);
+            }
+            other => panic!("Expected a trend command, got {other:?}"),
+        }
+    }
 }
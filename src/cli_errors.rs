@@ -0,0 +1,31 @@
+use git_moves_together::RepositoryError;
+use thiserror::Error as ThisError;
+
+/// Why a command failed
+#[derive(ThisError, Debug)]
+pub enum Error {
+    /// The library failed, such as to read a snapshot
+    #[error(transparent)]
+    Analysis(#[from] git_moves_together::Error),
+    /// History couldn't be read
+    #[error("git repository problem")]
+    Repository(#[from] RepositoryError),
+    /// A file such as the cache couldn't be read or written
+    #[error("failed to read or write file")]
+    Io(#[from] std::io::Error),
+    /// A report or the cache couldn't be turned into JSON or back
+    #[error("failed to read or write json")]
+    Json(#[from] serde_json::Error),
+    /// A report couldn't be written as CSV
+    #[error("failed to write csv")]
+    Csv(#[from] csv::Error),
+    /// A worker diffing commits panicked or was cancelled
+    #[error("a worker diffing commits stopped unexpectedly")]
+    Worker(#[from] tokio::task::JoinError),
+    /// An option needs to know how files changed, which the git log doesn't say
+    #[error("{0} needs a git log written with --name-status, which says how each file changed")]
+    KindsUnknown(&'static str),
+    /// Whatever was counting the deltas went away while they were being read
+    #[error("diffs stopped being counted before they were all read")]
+    Disconnected,
+}
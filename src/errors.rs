@@ -1,13 +1,18 @@
-use std::num::ParseIntError;
-
 use thiserror::Error as ThisError;
 
 use crate::repository::errors::Error as Repository;
 
+/// Why an analysis or one of its reports failed
 #[derive(ThisError, Debug)]
+#[non_exhaustive]
 pub enum Error {
+    /// History couldn't be read
     #[error("git repository problem")]
     Repository(#[from] Repository),
-    #[error("failed to parse days")]
-    DaysParse(#[from] ParseIntError),
+    /// A file such as a snapshot couldn't be read or written
+    #[error("failed to read or write file")]
+    Io(#[from] std::io::Error),
+    /// A report or snapshot couldn't be turned into JSON or back
+    #[error("failed to read or write json")]
+    Json(#[from] serde_json::Error),
 }
@@ -1,11 +1,106 @@
 use std::ops::Sub;
 
+use regex::Regex;
 use time::{Duration, OffsetDateTime};
 
-use crate::model::commit::Commit;
+use git_moves_together::Commit;
 
 pub fn within_time_limit(max_days: Option<i64>, commit: &Commit) -> bool {
     max_days.is_none_or(|max_days| {
         Duration::days(max_days).gt(&OffsetDateTime::now_utc().sub(commit.timestamp()))
     })
 }
+
+pub fn message_not_excluded(patterns: &[Regex], commit: &Commit) -> bool {
+    !patterns
+        .iter()
+        .any(|pattern| pattern.is_match(commit.message()))
+}
+
+pub fn type_included(types: &[String], commit: &Commit) -> bool {
+    types.is_empty()
+        || commit.conventional_type().is_some_and(|commit_type| {
+            types
+                .iter()
+                .any(|included| included.eq_ignore_ascii_case(commit_type))
+        })
+}
+
+pub fn author_not_excluded(patterns: &[Regex], commit: &Commit) -> bool {
+    !commit.authors().any(|author| {
+        let author = author.to_string();
+        patterns.iter().any(|pattern| pattern.is_match(&author))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use regex::Regex;
+    use time::OffsetDateTime;
+
+    use git_moves_together::{Author, Commit};
+
+    use super::{author_not_excluded, message_not_excluded, type_included};
+
+    fn commit(name: &str, message: &str) -> Commit {
+        Commit::new(
+            "1".into(),
+            vec![],
+            OffsetDateTime::now_utc(),
+            Author::new(name, &format!("{name}@example.com")),
+            message.into(),
+        )
+    }
+
+    #[test]
+    fn messages_matching_a_pattern_are_excluded() {
+        let patterns = vec![Regex::new("^(chore|style|ci)(\\(.*\\))?:").unwrap()];
+
+        assert!(!message_not_excluded(
+            &patterns,
+            &commit("alex", "chore(deps): bump")
+        ));
+        assert!(message_not_excluded(
+            &patterns,
+            &commit("alex", "feat: add thing")
+        ));
+    }
+
+    #[test]
+    fn only_included_conventional_types_are_kept() {
+        let types = vec!["feat".to_string(), "fix".to_string()];
+
+        assert!(type_included(&types, &commit("alex", "feat: add thing")));
+        assert!(type_included(
+            &types,
+            &commit("alex", "fix(parser)!: break")
+        ));
+        assert!(!type_included(&types, &commit("alex", "docs: explain")));
+        assert!(!type_included(&types, &commit("alex", "not conventional")));
+        assert!(type_included(&[], &commit("alex", "not conventional")));
+    }
+
+    #[test]
+    fn authors_matching_a_pattern_are_excluded() {
+        let patterns = vec![Regex::new("(?i)renovate|dependabot").unwrap()];
+
+        assert!(!author_not_excluded(
+            &patterns,
+            &commit("renovate[bot]", "fix: bump")
+        ));
+        assert!(author_not_excluded(&patterns, &commit("alex", "fix: bump")));
+    }
+
+    #[test]
+    fn co_authors_are_excluded_too() {
+        let patterns = vec![Regex::new("sam@example.com").unwrap()];
+
+        assert!(!author_not_excluded(
+            &patterns,
+            &commit(
+                "alex",
+                "fix: pair\n\nCo-authored-by: Sam <sam@example.com>\n"
+            )
+        ));
+    }
+}
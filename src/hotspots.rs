@@ -0,0 +1,236 @@
+use std::{
+    cmp::Ordering,
+    collections::BTreeMap,
+    fmt::{Display, Formatter},
+    io::Write,
+};
+
+use comfy_table::{ContentArrangement, Table, modifiers::UTF8_ROUND_CORNERS, presets::UTF8_FULL};
+use serde::Serialize;
+
+use crate::{model::changed_file::ChangedFile, statistics::Statistics};
+
+/// A file, how often it changed and how much it dragged others along
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Hotspot {
+    file: String,
+    changes: usize,
+    partners: usize,
+    coupling: f64,
+}
+
+impl Hotspot {
+    /// The file, as `repository@path` when it has a prefix
+    #[must_use]
+    pub fn file(&self) -> &str {
+        &self.file
+    }
+
+    /// How many grouped deltas the file was part of
+    #[must_use]
+    pub const fn changes(&self) -> usize {
+        self.changes
+    }
+
+    /// How many other files it changed together with at least once
+    #[must_use]
+    pub const fn partners(&self) -> usize {
+        self.partners
+    }
+
+    /// The sum of its together share with each partner
+    #[must_use]
+    pub const fn coupling(&self) -> f64 {
+        self.coupling
+    }
+}
+
+/// The files that change most often, most changed first
+#[derive(Debug)]
+pub struct Hotspots {
+    hotspots: Vec<Hotspot>,
+}
+
+impl Hotspots {
+    /// The hottest files in the statistics, only the `top` few if given
+    #[must_use]
+    pub fn new(statistics: &Statistics, top: Option<usize>) -> Self {
+        let mut partners: BTreeMap<&ChangedFile, (usize, f64)> = BTreeMap::new();
+        let coupling = statistics.coupling();
+        for (key, (score, _, _)) in &coupling {
+            for file in [key.left(), key.right()] {
+                let (count, strength) = partners.entry(file).or_default();
+                *count += 1;
+                *strength += score;
+            }
+        }
+
+        let mut hotspots: Vec<Hotspot> = statistics
+            .change_counts()
+            .map(|(file, changes)| {
+                let (partners, coupling) = partners.get(file).copied().unwrap_or_default();
+                Hotspot {
+                    file: file.clone().into(),
+                    changes,
+                    partners,
+                    coupling,
+                }
+            })
+            .collect();
+        hotspots.sort_by(hotspot_order);
+        hotspots.truncate(top.unwrap_or(hotspots.len()));
+
+        Self { hotspots }
+    }
+
+    /// Whether no file changed at all
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.hotspots.is_empty()
+    }
+
+    /// The hotspots, most changed first
+    pub fn iter(&self) -> std::slice::Iter<'_, Hotspot> {
+        self.hotspots.iter()
+    }
+
+    /// Write one row per hotspot, with a header
+    ///
+    /// # Errors
+    ///
+    /// When the writer fails
+    pub fn write_csv<W: Write>(&self, writer: W) -> Result<(), csv::Error> {
+        let mut writer = csv::Writer::from_writer(writer);
+        for hotspot in &self.hotspots {
+            writer.serialize(hotspot)?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Write the hotspots as a JSON array
+    ///
+    /// # Errors
+    ///
+    /// When the writer fails
+    pub fn write_json<W: Write>(&self, writer: W) -> Result<(), serde_json::Error> {
+        serde_json::to_writer_pretty(writer, &self.hotspots)
+    }
+}
+
+impl<'a> IntoIterator for &'a Hotspots {
+    type IntoIter = std::slice::Iter<'a, Hotspot>;
+    type Item = &'a Hotspot;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.hotspots.iter()
+    }
+}
+
+/// Most changed first, then the file that drags the most along with it
+fn hotspot_order(a: &Hotspot, b: &Hotspot) -> Ordering {
+    b.changes
+        .cmp(&a.changes)
+        .then_with(|| {
+            b.coupling
+                .partial_cmp(&a.coupling)
+                .unwrap_or(Ordering::Equal)
+        })
+        .then_with(|| a.file.cmp(&b.file))
+}
+
+impl Display for Hotspots {
+    fn fmt(&self, formatter: &mut Formatter<'_>) -> std::fmt::Result {
+        let mut table = Table::new();
+        table
+            .load_preset(UTF8_FULL)
+            .apply_modifier(UTF8_ROUND_CORNERS)
+            .set_content_arrangement(ContentArrangement::Dynamic)
+            .set_header(vec!["File", "Changes", "Partners", "Coupling"]);
+        for hotspot in &self.hotspots {
+            table.add_row(vec![
+                hotspot.file.clone(),
+                format!("{}", hotspot.changes),
+                format!("{}", hotspot.partners),
+                format!("{:.2}", hotspot.coupling),
+            ]);
+        }
+
+        writeln!(formatter, "{table}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use time::OffsetDateTime;
+
+    use crate::{
+        hotspots::{Hotspot, Hotspots},
+        model::delta::Delta,
+        statistics::{Statistics, Strategy},
+    };
+
+    fn statistics() -> Statistics {
+        [
+            vec!["file_1", "file_2"],
+            vec!["file_1", "file_3"],
+            vec!["file_1"],
+            vec!["file_4"],
+        ]
+        .into_iter()
+        .enumerate()
+        .fold(Statistics::default(), |statistics, (id, files)| {
+            statistics.add_delta(
+                &Delta::new(
+                    id.to_string().into(),
+                    OffsetDateTime::now_utc(),
+                    files.into_iter().map(Into::into).collect(),
+                ),
+                &Strategy::Hash,
+            )
+        })
+    }
+
+    #[test]
+    fn files_are_ranked_by_how_often_they_change() {
+        let hotspots = Hotspots::new(&statistics(), None);
+
+        assert_eq!(
+            hotspots.hotspots,
+            vec![
+                Hotspot {
+                    file: "file_1".into(),
+                    changes: 3,
+                    partners: 2,
+                    coupling: 2.0 / 3.0,
+                },
+                Hotspot {
+                    file: "file_2".into(),
+                    changes: 1,
+                    partners: 1,
+                    coupling: 1.0 / 3.0,
+                },
+                Hotspot {
+                    file: "file_3".into(),
+                    changes: 1,
+                    partners: 1,
+                    coupling: 1.0 / 3.0,
+                },
+                Hotspot {
+                    file: "file_4".into(),
+                    changes: 1,
+                    partners: 0,
+                    coupling: 0.0,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn only_the_top_files_are_kept() {
+        let hotspots = Hotspots::new(&statistics(), Some(1));
+
+        assert_eq!(hotspots.hotspots.len(), 1);
+        assert_eq!(hotspots.hotspots[0].file, "file_1");
+    }
+}
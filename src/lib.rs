@@ -0,0 +1,96 @@
+//! Find files that commonly appear in the same time slice or commit
+//!
+//! Read the history of a [`Repository`] one commit at a time as [`Delta`]s,
+//! add them to [`Statistics`] with a [`Strategy`] that decides which commits
+//! count as changing together, and ask the statistics for a
+//! [`CouplingResult`]. [`Hotspots`], [`Trend`], [`Lifecycle`] and
+//! [`Snapshot`] are other views of the same deltas.
+//!
+//! ```
+//! use git_moves_together::{
+//!     Author, Change, Commit, Commits, InMemory, Repository, Statistics, Strategy,
+//! };
+//! use time::OffsetDateTime;
+//!
+//! let commit = Commit::new(
+//!     "1".into(),
+//!     vec![],
+//!     OffsetDateTime::UNIX_EPOCH,
+//!     Author::new("John Doe", "john@doe.org"),
+//!     "Add both files\n".into(),
+//! );
+//! let repository = InMemory::new(
+//!     Commits::from(vec![commit.clone()]),
+//!     vec![
+//!         ("1".into(), "file_1".into(), Change::default()),
+//!         ("1".into(), "file_2".into(), Change::default()),
+//!     ],
+//! );
+//!
+//! let mut statistics = Statistics::default();
+//! for commit in repository.commits_in_current_branch()?.iter() {
+//!     let delta = repository.compare_with_parent(commit)?;
+//!     statistics = statistics.add_delta(&delta, &Strategy::Hash);
+//! }
+//! let coupling = statistics.coupling();
+//!
+//! let (key, (strength, together, total)) = coupling.iter().next().unwrap();
+//! assert_eq!(String::from(key.left().clone()), "file_1");
+//! assert_eq!((*strength, *together, *total), (1.0, 1, 1));
+//! # Ok::<(), git_moves_together::RepositoryError>(())
+//! ```
+
+#![warn(clippy::nursery)]
+#![deny(
+    unused,
+    nonstandard_style,
+    future_incompatible,
+    missing_copy_implementations,
+    missing_debug_implementations,
+    missing_docs,
+    clippy::cargo,
+    clippy::complexity,
+    clippy::correctness,
+    clippy::perf,
+    clippy::style,
+    clippy::suspicious,
+    clippy::pedantic,
+    non_fmt_panics
+)]
+#![allow(clippy::multiple_crate_versions)]
+
+mod errors;
+mod hotspots;
+mod lifecycle;
+mod model;
+mod repository;
+mod snapshot;
+mod statistics;
+mod trend;
+
+pub use errors::Error;
+pub use hotspots::{Hotspot, Hotspots};
+pub use lifecycle::{Event, Lifecycle};
+pub use model::{
+    author::Author,
+    change::{Change, Kind},
+    changed_file::ChangedFile,
+    commit::Commit,
+    commits::Commits,
+    delta::Delta,
+    hash::Hash,
+};
+#[cfg(feature = "gix")]
+pub use repository::gix::Gix;
+#[cfg(feature = "libgit2")]
+pub use repository::libgit2::LibGit2;
+pub use repository::{
+    errors::Error as RepositoryError,
+    git_log::GitLog,
+    in_memory::InMemory,
+    interface::{OnDisk, Repository},
+    options::{Options, WalkProgress},
+};
+pub use snapshot::{Snapshot, SnapshotDiff};
+pub use statistics::{Calculation, Churn, CouplingResult, Key, Statistics, Strategy};
+pub use trend::{Period, Point, Trend};
@@ -0,0 +1,220 @@
+use std::{
+    fmt::{Display, Formatter},
+    io::Write,
+};
+
+use comfy_table::{ContentArrangement, Table, modifiers::UTF8_ROUND_CORNERS, presets::UTF8_FULL};
+use serde::Serialize;
+use time::UtcOffset;
+
+use crate::model::{change::Kind, delta::Delta};
+
+/// A file being added, deleted or renamed by a commit
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct Event {
+    date: String,
+    commit: String,
+    kind: Kind,
+    file: String,
+}
+
+impl Event {
+    /// The day of the commit in UTC, as `YYYY-MM-DD`
+    #[must_use]
+    pub fn date(&self) -> &str {
+        &self.date
+    }
+
+    /// The id of the commit
+    #[must_use]
+    pub fn commit(&self) -> &str {
+        &self.commit
+    }
+
+    /// What happened to the file
+    #[must_use]
+    pub const fn kind(&self) -> Kind {
+        self.kind
+    }
+
+    /// The file, by its new path for a rename
+    #[must_use]
+    pub fn file(&self) -> &str {
+        &self.file
+    }
+}
+
+/// When files were added, deleted or renamed, kept apart from coupling
+#[derive(Debug)]
+pub struct Lifecycle {
+    events: Vec<Event>,
+}
+
+impl Lifecycle {
+    /// The lifecycle events in these deltas, oldest first
+    #[must_use]
+    pub fn new(deltas: &[Delta]) -> Self {
+        let mut deltas: Vec<&Delta> = deltas.iter().collect();
+        deltas.sort_by_key(|delta| (delta.timestamp(), delta.hash().clone()));
+
+        Self {
+            events: deltas
+                .into_iter()
+                .flat_map(|delta| {
+                    delta
+                        .changes()
+                        .iter()
+                        .filter(|(_, change)| change.kind().is_lifecycle())
+                        .map(|(file, change)| Event {
+                            date: delta
+                                .timestamp()
+                                .to_offset(UtcOffset::UTC)
+                                .date()
+                                .to_string(),
+                            commit: delta.hash().clone().into(),
+                            kind: change.kind(),
+                            file: file.clone().into(),
+                        })
+                })
+                .collect(),
+        }
+    }
+
+    /// Whether no file was added, deleted or renamed
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+
+    /// The events, oldest first
+    pub fn iter(&self) -> std::slice::Iter<'_, Event> {
+        self.events.iter()
+    }
+
+    /// Write one row per event, with a header
+    ///
+    /// # Errors
+    ///
+    /// When the writer fails
+    pub fn write_csv<W: Write>(&self, writer: W) -> Result<(), csv::Error> {
+        let mut writer = csv::Writer::from_writer(writer);
+        for event in &self.events {
+            writer.serialize(event)?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Write the events as a JSON array
+    ///
+    /// # Errors
+    ///
+    /// When the writer fails
+    pub fn write_json<W: Write>(&self, writer: W) -> Result<(), serde_json::Error> {
+        serde_json::to_writer_pretty(writer, &self.events)
+    }
+}
+
+impl<'a> IntoIterator for &'a Lifecycle {
+    type IntoIter = std::slice::Iter<'a, Event>;
+    type Item = &'a Event;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.events.iter()
+    }
+}
+
+impl Display for Lifecycle {
+    fn fmt(&self, formatter: &mut Formatter<'_>) -> std::fmt::Result {
+        let mut table = Table::new();
+        table
+            .load_preset(UTF8_FULL)
+            .apply_modifier(UTF8_ROUND_CORNERS)
+            .set_content_arrangement(ContentArrangement::Dynamic)
+            .set_header(vec!["Date", "Commit", "Change", "File"]);
+        for event in &self.events {
+            table.add_row(vec![
+                event.date.clone(),
+                event.commit.chars().take(7).collect(),
+                event.kind.to_string(),
+                event.file.clone(),
+            ]);
+        }
+
+        writeln!(formatter, "{table}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use time::{Date, Month, OffsetDateTime};
+
+    use crate::{
+        lifecycle::{Event, Lifecycle},
+        model::{
+            author::Author,
+            change::{Change, Kind},
+            commit::Commit,
+            delta::Delta,
+        },
+    };
+
+    fn at(day: u8) -> OffsetDateTime {
+        Date::from_calendar_date(2024, Month::January, day)
+            .unwrap()
+            .midnight()
+            .assume_utc()
+    }
+
+    fn delta(hash: &str, day: u8, changes: Vec<(&str, Kind)>) -> Delta {
+        Delta::for_commit(
+            &Commit::new(
+                hash.into(),
+                vec![],
+                at(day),
+                Author::new("John Doe", "john@doe.org"),
+                String::new(),
+            ),
+            changes
+                .into_iter()
+                .map(|(file, kind)| (file.into(), Change::default().with_kind(kind)))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn only_lifecycle_events_are_reported_oldest_first() {
+        let lifecycle = Lifecycle::new(&[
+            delta(
+                "2",
+                5,
+                vec![("old", Kind::Deleted), ("edited", Kind::Modified)],
+            ),
+            delta("1", 3, vec![("old", Kind::Added), ("new", Kind::Added)]),
+        ]);
+
+        assert_eq!(
+            lifecycle.events,
+            vec![
+                Event {
+                    date: "2024-01-03".into(),
+                    commit: "1".into(),
+                    kind: Kind::Added,
+                    file: "new".into(),
+                },
+                Event {
+                    date: "2024-01-03".into(),
+                    commit: "1".into(),
+                    kind: Kind::Added,
+                    file: "old".into(),
+                },
+                Event {
+                    date: "2024-01-05".into(),
+                    commit: "2".into(),
+                    kind: Kind::Deleted,
+                    file: "old".into(),
+                },
+            ]
+        );
+    }
+}
@@ -1,4 +1,5 @@
-//! Find files that commonly appear in the same time slice or commit
+//! Find files that commonly appear in the same time slice or commit, from
+//! the command line
 
 #![warn(clippy::nursery)]
 #![deny(
@@ -19,75 +20,178 @@
 )]
 #![allow(clippy::multiple_crate_versions)]
 
+#[cfg(not(any(feature = "libgit2", feature = "gix")))]
+compile_error!("git-moves-together needs the libgit2 or gix feature to read repositories");
+
 use clap::Parser;
+mod cache;
 mod cli;
-mod errors;
+mod cli_errors;
 mod filters;
-mod model;
-mod repository;
-mod statistics;
+mod progress;
+mod read;
 
 use std::path::PathBuf;
 
-use futures::{StreamExt, TryStreamExt, stream};
-use model::delta::Delta;
-use repository::interface::Repository;
+#[cfg(feature = "gix")]
+use git_moves_together::Gix;
+#[cfg(feature = "libgit2")]
+use git_moves_together::LibGit2;
+use git_moves_together::{
+    CouplingResult, Hotspots, Lifecycle, OnDisk, Options, Snapshot, Statistics, Strategy, Trend,
+};
 use time::Duration;
 
 use crate::{
-    cli::Args,
-    errors::Error,
-    repository::libgit2::LibGit2,
-    statistics::{Statistics, Strategy},
+    cache::Cache,
+    cli::{Analysis, Args, Backend, CacheAction, Command, Format},
+    cli_errors::Error,
+    read::{for_each_delta, read_all_deltas},
 };
 
 #[tokio::main]
-async fn main() -> Result<(), crate::errors::Error> {
+async fn main() -> Result<(), Error> {
     miette::set_panic_hook();
     let args = Args::parse();
 
-    let strategy = args.time_window_minutes.map_or(Strategy::Hash, |value| {
-        Strategy::CommitTime(Duration::minutes(value))
-    });
-
-    let deltas: Vec<Vec<Delta>> = stream::iter(args.git_repo.iter())
-        .map(|path_str| read_deltas(args.max_days_ago, path_str))
-        .try_collect()
-        .await?;
+    match args.command {
+        None => {
+            let coupling = analyse(args.analysis).await?;
+            if coupling.is_empty() {
+                println!("0 files move together");
+            } else {
+                print!("{coupling}");
+            }
+        }
+        Some(Command::Snapshot { output, analysis }) => {
+            let coupling = analyse(analysis).await?;
+            Snapshot::from(&coupling).write(&output)?;
+        }
+        Some(Command::Diff {
+            old,
+            new,
+            min_delta,
+        }) => {
+            let diff = Snapshot::read(&old)?.diff(&Snapshot::read(&new)?, min_delta);
+            if diff.is_empty() {
+                println!("0 pairs changed");
+            } else {
+                print!("{diff}");
+            }
+        }
+        Some(Command::Trend {
+            period,
+            top,
+            format,
+            analysis,
+        }) => {
+            let strategy = strategy(&analysis);
+            let deltas = read_all_deltas(analysis).await?;
+            let trend = Trend::new(&deltas, &strategy, period, top);
+            match format {
+                Format::Table if trend.is_empty() => println!("0 files move together"),
+                Format::Table => print!("{trend}"),
+                Format::Csv => trend.write_csv(std::io::stdout())?,
+                Format::Json => {
+                    trend.write_json(std::io::stdout())?;
+                    println!();
+                }
+            }
+        }
+        Some(Command::Hotspots {
+            top,
+            format,
+            analysis,
+        }) => {
+            let hotspots = Hotspots::new(&statistics(analysis).await?, top);
+            match format {
+                Format::Table if hotspots.is_empty() => println!("0 files changed"),
+                Format::Table => print!("{hotspots}"),
+                Format::Csv => hotspots.write_csv(std::io::stdout())?,
+                Format::Json => {
+                    hotspots.write_json(std::io::stdout())?;
+                    println!();
+                }
+            }
+        }
+        Some(Command::Cache {
+            action: CacheAction::Clear { git_repo },
+        }) => {
+            for path_str in git_repo {
+                Cache::clear(&git_dir(PathBuf::from(path_str))?)?;
+            }
+        }
+        Some(Command::Lifecycle { format, analysis }) => {
+            let lifecycle = Lifecycle::new(
+                &read_all_deltas(Analysis {
+                    lifecycle: true,
+                    ..analysis
+                })
+                .await?,
+            );
+            match format {
+                Format::Table if lifecycle.is_empty() => println!("0 lifecycle events"),
+                Format::Table => print!("{lifecycle}"),
+                Format::Csv => lifecycle.write_csv(std::io::stdout())?,
+                Format::Json => {
+                    lifecycle.write_json(std::io::stdout())?;
+                    println!();
+                }
+            }
+        }
+    }
 
-    let statistics = deltas
-        .into_iter()
-        .zip(args.git_repo)
-        .flat_map(|(delta, prefix)| add_prefix((&delta, &prefix)))
-        .fold(Statistics::default(), |statistics, change_delta| {
-            statistics.add_delta(&change_delta, &strategy)
-        });
+    Ok(())
+}
 
+async fn analyse(analysis: Analysis) -> Result<CouplingResult, Error> {
+    let churn = analysis.churn;
+    let statistics = statistics(analysis).await?;
     let coupling = statistics.coupling();
-    if coupling.is_empty() {
-        println!("0 files move together");
+    Ok(if churn {
+        coupling.with_churn(&statistics)
     } else {
-        print!("{coupling}");
-    }
+        coupling
+    })
+}
 
-    Ok(())
+async fn statistics(analysis: Analysis) -> Result<Statistics, Error> {
+    let strategy = strategy(&analysis);
+    let mut statistics = Statistics::default();
+    for_each_delta(analysis, |delta| {
+        statistics = std::mem::take(&mut statistics).add_delta(&delta, &strategy);
+    })
+    .await?;
+    Ok(statistics)
 }
 
-fn add_prefix((delta, prefix): (&Vec<Delta>, &str)) -> Vec<Delta> {
-    delta
-        .iter()
-        .map(|delta| delta.add_prefix(prefix))
-        .collect::<Vec<_>>()
+/// Where git keeps a repository's own files, found with the default backend
+fn git_dir(path: PathBuf) -> Result<PathBuf, Error> {
+    Ok(match Backend::default() {
+        #[cfg(feature = "libgit2")]
+        Backend::Libgit2 => LibGit2::open(path, Options::default())?
+            .git_dir()
+            .to_path_buf(),
+        #[cfg(feature = "gix")]
+        Backend::Gix => Gix::open(path, Options::default())?.git_dir().to_path_buf(),
+    })
 }
 
-fn read_deltas(max_days: Option<i64>, path_str: &str) -> Result<Vec<Delta>, Error> {
-    let path = PathBuf::from(path_str);
-    let repo = LibGit2::new(path)?;
-    let commits = repo.commits_in_current_branch()?;
-    commits
-        .iter()
-        .filter(|commit| filters::within_time_limit(max_days, commit))
-        .map(|commit| repo.clone().compare_with_parent(commit))
-        .collect::<Result<Vec<_>, _>>()
-        .map_err(Error::from)
+fn strategy(analysis: &Analysis) -> Strategy {
+    if let Some(pattern) = &analysis.ticket_pattern {
+        return Strategy::Ticket(pattern.clone());
+    }
+    if analysis.by_pull_request {
+        return Strategy::PullRequest;
+    }
+
+    match (
+        analysis.time_window_minutes,
+        analysis.sliding_window_minutes,
+    ) {
+        (Some(value), _) if analysis.per_author => Strategy::AuthorTime(Duration::minutes(value)),
+        (Some(value), _) => Strategy::CommitTime(Duration::minutes(value)),
+        (None, Some(value)) => Strategy::SlidingWindow(Duration::minutes(value)),
+        (None, None) => Strategy::Hash,
+    }
 }
@@ -0,0 +1,142 @@
+use std::fmt::{Display, Formatter};
+
+#[cfg(feature = "libgit2")]
+use git2::{Mailmap, Signature, Time};
+
+/// Someone who wrote a commit
+#[derive(Debug, Eq, PartialEq, Clone, Hash, Ord, PartialOrd)]
+pub struct Author {
+    name: String,
+    email: String,
+}
+
+impl Author {
+    /// Someone with this name and email
+    #[must_use]
+    pub fn new(name: &str, email: &str) -> Self {
+        Self {
+            name: name.into(),
+            email: email.into(),
+        }
+    }
+
+    /// Parse an identity written the way git writes it, `Name <email>`
+    #[must_use]
+    pub fn parse(identity: &str) -> Option<Self> {
+        let (name, rest) = identity.split_once('<')?;
+        let (email, _) = rest.split_once('>')?;
+        let (name, email) = (name.trim(), email.trim());
+
+        if name.is_empty() || email.is_empty() {
+            None
+        } else {
+            Some(Self::new(name, email))
+        }
+    }
+}
+
+#[cfg(feature = "libgit2")]
+impl Author {
+    /// Resolve this identity through a mailmap, keeping it as is if it
+    /// can't be expressed as a signature
+    pub(crate) fn resolve(&self, mailmap: &Mailmap) -> Self {
+        Signature::new(&self.name, &self.email, &Time::new(0, 0))
+            .and_then(|signature| mailmap.resolve_signature(&signature))
+            .map_or_else(|_| self.clone(), Self::from)
+    }
+}
+
+#[cfg(feature = "libgit2")]
+impl From<Signature<'_>> for Author {
+    fn from(signature: Signature<'_>) -> Self {
+        Self::new(
+            &String::from_utf8_lossy(signature.name_bytes()),
+            &String::from_utf8_lossy(signature.email_bytes()),
+        )
+    }
+}
+
+#[cfg(feature = "gix")]
+impl Author {
+    /// Resolve this identity through a gitoxide mailmap
+    pub(crate) fn resolve_gix(&self, mailmap: &gix::mailmap::Snapshot) -> Self {
+        mailmap
+            .resolve_cow(gix::actor::SignatureRef {
+                name: self.name.as_str().into(),
+                email: self.email.as_str().into(),
+                time: "",
+            })
+            .into()
+    }
+}
+
+#[cfg(feature = "gix")]
+impl From<gix::mailmap::snapshot::Signature<'_>> for Author {
+    fn from(signature: gix::mailmap::snapshot::Signature<'_>) -> Self {
+        Self::new(
+            &String::from_utf8_lossy(&signature.name),
+            &String::from_utf8_lossy(&signature.email),
+        )
+    }
+}
+
+impl Display for Author {
+    fn fmt(&self, formatter: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(formatter, "{} <{}>", self.name, self.email)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "libgit2")]
+    use git2::{Mailmap, Signature};
+
+    use super::Author;
+
+    #[test]
+    fn displays_like_git() {
+        assert_eq!(
+            Author::new("John Doe", "john@doe.org").to_string(),
+            "John Doe <john@doe.org>"
+        );
+    }
+
+    #[test]
+    fn can_be_parsed_from_how_git_writes_it() {
+        assert_eq!(
+            Author::parse(" John Doe <john@doe.org> "),
+            Some(Author::new("John Doe", "john@doe.org"))
+        );
+        assert_eq!(Author::parse("John Doe"), None);
+        assert_eq!(Author::parse("<john@doe.org>"), None);
+    }
+
+    #[cfg(feature = "libgit2")]
+    #[test]
+    fn can_be_resolved_through_a_mailmap() {
+        let mut mailmap = Mailmap::new().unwrap();
+        mailmap
+            .add_entry(
+                Some("John Doe"),
+                Some("john@doe.org"),
+                None,
+                "johnny@home.example",
+            )
+            .unwrap();
+
+        assert_eq!(
+            Author::new("Johnny", "johnny@home.example").resolve(&mailmap),
+            Author::new("John Doe", "john@doe.org")
+        );
+    }
+
+    #[cfg(feature = "libgit2")]
+    #[test]
+    fn can_be_made_from_a_signature() {
+        let signature = Signature::now("John Doe", "john@doe.org").unwrap();
+        assert_eq!(
+            Author::from(signature),
+            Author::new("John Doe", "john@doe.org")
+        );
+    }
+}
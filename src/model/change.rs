@@ -0,0 +1,155 @@
+use std::fmt::{Display, Formatter};
+
+#[cfg(feature = "libgit2")]
+use git2::Delta as Status;
+use serde::{Deserialize, Serialize};
+
+/// How a file took part in a delta. Ordered so that combining changes keeps
+/// the lifecycle event over a plain modification.
+#[derive(
+    Serialize, Deserialize, Debug, Default, Eq, PartialEq, Clone, Copy, Hash, Ord, PartialOrd,
+)]
+#[serde(rename_all = "lowercase")]
+#[non_exhaustive]
+pub enum Kind {
+    /// The file's content or mode changed
+    #[default]
+    Modified,
+    /// The file moved, and is known by its new path
+    Renamed,
+    /// The file appeared, including as a copy of another
+    Added,
+    /// The file went away, and is known by its old path
+    Deleted,
+}
+
+impl Kind {
+    /// Whether the file came, went or moved rather than just being edited
+    #[must_use]
+    pub const fn is_lifecycle(self) -> bool {
+        !matches!(self, Self::Modified)
+    }
+}
+
+#[cfg(feature = "libgit2")]
+impl From<Status> for Kind {
+    fn from(status: Status) -> Self {
+        match status {
+            Status::Added | Status::Copied => Self::Added,
+            Status::Deleted => Self::Deleted,
+            Status::Renamed => Self::Renamed,
+            _ => Self::Modified,
+        }
+    }
+}
+
+impl Display for Kind {
+    fn fmt(&self, formatter: &mut Formatter<'_>) -> std::fmt::Result {
+        let kind = match self {
+            Self::Modified => "Modified",
+            Self::Renamed => "Renamed",
+            Self::Added => "Added",
+            Self::Deleted => "Deleted",
+        };
+        write!(formatter, "{kind}")
+    }
+}
+
+/// What happened to a single file in a delta
+#[derive(
+    Serialize, Deserialize, Debug, Default, Eq, PartialEq, Clone, Copy, Hash, Ord, PartialOrd,
+)]
+pub struct Change {
+    kind: Kind,
+    binary: bool,
+    added_lines: usize,
+    removed_lines: usize,
+}
+
+impl Change {
+    /// A modification of a text file that added and removed these lines
+    #[must_use]
+    pub const fn new(added_lines: usize, removed_lines: usize) -> Self {
+        Self {
+            kind: Kind::Modified,
+            binary: false,
+            added_lines,
+            removed_lines,
+        }
+    }
+
+    /// The same change, but happening in this way
+    #[must_use]
+    pub const fn with_kind(mut self, kind: Kind) -> Self {
+        self.kind = kind;
+        self
+    }
+
+    /// The same change, but to a file git does or doesn't consider binary
+    #[must_use]
+    pub const fn with_binary(mut self, binary: bool) -> Self {
+        self.binary = binary;
+        self
+    }
+
+    /// The same change, with the lines it added and removed left uncounted
+    #[must_use]
+    pub const fn without_lines(mut self) -> Self {
+        self.added_lines = 0;
+        self.removed_lines = 0;
+        self
+    }
+
+    /// How the file took part
+    #[must_use]
+    pub const fn kind(&self) -> Kind {
+        self.kind
+    }
+
+    /// Whether git considers the file binary, which is only known when the
+    /// repository was asked to find out
+    #[must_use]
+    pub const fn is_binary(&self) -> bool {
+        self.binary
+    }
+
+    /// Lines added plus lines removed
+    #[must_use]
+    pub const fn churn(&self) -> usize {
+        self.added_lines + self.removed_lines
+    }
+
+    /// The same file changed more than once in a grouped delta
+    #[must_use]
+    pub fn combine(self, other: Self) -> Self {
+        Self {
+            kind: self.kind.max(other.kind),
+            binary: self.binary || other.binary,
+            added_lines: self.added_lines + other.added_lines,
+            removed_lines: self.removed_lines + other.removed_lines,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::change::{Change, Kind};
+
+    #[cfg(feature = "libgit2")]
+    #[test]
+    fn copies_count_as_additions() {
+        use git2::Delta as Status;
+
+        assert_eq!(Kind::from(Status::Copied), Kind::Added);
+        assert_eq!(Kind::from(Status::Typechange), Kind::Modified);
+    }
+
+    #[test]
+    fn combining_keeps_the_lifecycle_event() {
+        let combined = Change::new(1, 0)
+            .with_kind(Kind::Added)
+            .combine(Change::new(2, 3));
+
+        assert_eq!(combined, Change::new(3, 3).with_kind(Kind::Added));
+    }
+}
@@ -1,18 +1,34 @@
-use git2::DiffDelta;
+#[cfg(feature = "libgit2")]
+use git2::{Delta as Status, DiffDelta};
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Eq, PartialEq, Clone, Hash, Ord, PartialOrd)]
+/// A path in a repository, prefixed with the repository's name when several
+/// are analysed together so their files can't be confused. Written as
+/// `repository@path` when it has a prefix.
+#[derive(Serialize, Deserialize, Debug, Eq, PartialEq, Clone, Hash, Ord, PartialOrd)]
 pub struct ChangedFile {
     prefix: Option<String>,
     path: String,
 }
 
 impl ChangedFile {
-    pub(crate) fn add_prefix(&self, prefix: &str) -> Self {
+    /// The same path in the repository with this name
+    #[must_use]
+    pub fn add_prefix(&self, prefix: &str) -> Self {
         Self {
             prefix: Some(prefix.into()),
             path: self.path.clone(),
         }
     }
+
+    /// The same file seen from a repository that has this one's as a subdirectory
+    #[must_use]
+    pub fn in_directory(&self, directory: &str) -> Self {
+        Self {
+            prefix: self.prefix.clone(),
+            path: format!("{directory}/{}", self.path),
+        }
+    }
 }
 
 impl From<&str> for ChangedFile {
@@ -45,11 +61,16 @@ impl From<ChangedFile> for String {
     }
 }
 
+#[cfg(feature = "libgit2")]
 impl From<DiffDelta<'_>> for ChangedFile {
     fn from(delta: DiffDelta<'_>) -> Self {
-        delta
-            .new_file()
-            .path()
+        // A deleted file only has a path on the old side of the diff
+        let file = if delta.status() == Status::Deleted {
+            delta.old_file()
+        } else {
+            delta.new_file()
+        };
+        file.path()
             .map(|p| p.to_string_lossy().into_owned())
             .map_or_else(|| Self::from("?"), Self::from)
     }
@@ -1,44 +1,200 @@
-use git2::Commit as Git2Commit;
+#[cfg(feature = "libgit2")]
+use git2::{Commit as Git2Commit, Mailmap};
 use time::OffsetDateTime;
 
-use crate::model::hash::Hash;
+use crate::model::{author::Author, hash::Hash};
 
+/// A commit as far as coupling is concerned: when it was made, by whom, why,
+/// and what it came from
 #[derive(Eq, PartialEq, Debug, Clone)]
 pub struct Commit {
     hash: Hash,
     timestamp: OffsetDateTime,
     parents: Vec<Hash>,
+    author: Author,
+    co_authors: Vec<Author>,
+    message: String,
 }
 
 impl Commit {
-    pub(crate) const fn new(hash: Hash, parents: Vec<Hash>, timestamp: OffsetDateTime) -> Self {
+    /// A commit, crediting anyone in a `Co-authored-by` trailer of its
+    /// message as well as its author
+    #[must_use]
+    pub fn new(
+        hash: Hash,
+        parents: Vec<Hash>,
+        timestamp: OffsetDateTime,
+        author: Author,
+        message: String,
+    ) -> Self {
         Self {
             hash,
             timestamp,
             parents,
+            author,
+            co_authors: co_authors(&message),
+            message,
         }
     }
 
-    pub(crate) const fn hash(&self) -> &Hash {
+    /// Build a commit from libgit2, resolving the author and co-authors
+    /// through the repository's mailmap so one person with several emails
+    /// is one author
+    #[cfg(feature = "libgit2")]
+    pub(crate) fn from_git2(
+        commit: &Git2Commit<'_>,
+        mailmap: &Mailmap,
+    ) -> Result<Self, git2::Error> {
+        let commit = Self::new(
+            commit.id().into(),
+            commit.parents().map(|parent| parent.id().into()).collect(),
+            OffsetDateTime::from_unix_timestamp(commit.time().seconds())
+                .expect("Timestamp would overflow integer"),
+            commit.author_with_mailmap(mailmap)?.into(),
+            String::from_utf8_lossy(commit.message_bytes()).into_owned(),
+        );
+
+        Ok(Self {
+            co_authors: commit
+                .co_authors
+                .iter()
+                .map(|co_author| co_author.resolve(mailmap))
+                .collect(),
+            ..commit
+        })
+    }
+
+    /// Build a commit from gitoxide, resolving the author and co-authors
+    /// through the repository's mailmap, the same as with libgit2
+    #[cfg(feature = "gix")]
+    pub(crate) fn from_gix(
+        commit: &gix::Commit<'_>,
+        mailmap: &gix::mailmap::Snapshot,
+    ) -> Result<Self, gix::object::commit::Error> {
+        let commit = Self::new(
+            commit.id.into(),
+            commit
+                .parent_ids()
+                .map(|parent| parent.detach().into())
+                .collect(),
+            OffsetDateTime::from_unix_timestamp(commit.time()?.seconds)
+                .expect("Timestamp would overflow integer"),
+            mailmap.resolve_cow(commit.author()?).into(),
+            String::from_utf8_lossy(commit.message_raw()?).into_owned(),
+        );
+
+        Ok(Self {
+            co_authors: commit
+                .co_authors
+                .iter()
+                .map(|co_author| co_author.resolve_gix(mailmap))
+                .collect(),
+            ..commit
+        })
+    }
+
+    /// The commit's id
+    #[must_use]
+    pub const fn hash(&self) -> &Hash {
         &self.hash
     }
 
-    pub(crate) const fn timestamp(&self) -> OffsetDateTime {
+    /// When the commit was made, by the committer's clock
+    #[must_use]
+    pub const fn timestamp(&self) -> OffsetDateTime {
         self.timestamp
     }
 
-    pub(crate) fn parents(&self) -> &[Hash] {
+    /// The commits this one came from, first parent first
+    #[must_use]
+    pub fn parents(&self) -> &[Hash] {
         &self.parents
     }
+
+    /// The same commit without its other parents, so a merge is compared
+    /// only with the mainline it was merged into
+    #[must_use]
+    pub fn first_parent_only(mut self) -> Self {
+        self.parents.truncate(1);
+        self
+    }
+
+    /// The author and everyone credited in a `Co-authored-by` trailer
+    pub fn authors(&self) -> impl Iterator<Item = &Author> {
+        std::iter::once(&self.author).chain(&self.co_authors)
+    }
+
+    /// The full commit message
+    #[must_use]
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// The type from a conventional commit subject, such as `feat` in
+    /// `feat(parser)!: add a thing`
+    #[must_use]
+    pub fn conventional_type(&self) -> Option<&str> {
+        let (prefix, _) = self.message.lines().next()?.split_once(':')?;
+        let prefix = prefix.strip_suffix('!').unwrap_or(prefix);
+        let commit_type = prefix
+            .split_once('(')
+            .map_or(prefix, |(commit_type, _)| commit_type);
+
+        if !commit_type.is_empty() && commit_type.chars().all(|c| c.is_ascii_alphanumeric()) {
+            Some(commit_type)
+        } else {
+            None
+        }
+    }
 }
 
-impl From<Git2Commit<'_>> for Commit {
-    fn from(commit: Git2Commit<'_>) -> Self {
-        Self::new(
-            commit.id().into(),
-            commit.parents().map(|parent| parent.id().into()).collect(),
-            OffsetDateTime::from_unix_timestamp(commit.time().seconds())
-                .expect("Timestamp would overflow integer"),
+fn co_authors(message: &str) -> Vec<Author> {
+    message
+        .lines()
+        .filter_map(|line| line.split_once(':'))
+        .filter(|(token, _)| token.trim().eq_ignore_ascii_case("co-authored-by"))
+        .filter_map(|(_, identity)| Author::parse(identity))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use time::OffsetDateTime;
+
+    use crate::model::{author::Author, commit::Commit};
+
+    fn commit(message: &str) -> Commit {
+        Commit::new(
+            "1".into(),
+            vec![],
+            OffsetDateTime::now_utc(),
+            Author::new("John Doe", "john@doe.org"),
+            message.into(),
         )
     }
+
+    #[test]
+    fn co_authors_are_read_from_trailers() {
+        let commit = commit(
+            "feat: pair on a thing\n\nCo-authored-by: Sam Smith <sam@example.com>\nco-authored-by: Alex <alex@example.com>\n",
+        );
+
+        assert_eq!(
+            commit.authors().cloned().collect::<Vec<_>>(),
+            vec![
+                Author::new("John Doe", "john@doe.org"),
+                Author::new("Sam Smith", "sam@example.com"),
+                Author::new("Alex", "alex@example.com"),
+            ]
+        );
+    }
+
+    #[test]
+    fn conventional_types_are_read_from_the_subject() {
+        assert_eq!(
+            commit("feat(parser)!: add").conventional_type(),
+            Some("feat")
+        );
+        assert_eq!(commit("Fix the parser").conventional_type(), None);
+    }
 }
@@ -1,5 +1,8 @@
-use crate::model::commit::Commit;
+use std::collections::{BTreeMap, BTreeSet};
 
+use crate::model::{commit::Commit, hash::Hash};
+
+/// The commits of a branch, newest first
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub struct Commits {
     commits: Vec<Commit>,
@@ -20,8 +23,134 @@ impl IntoIterator for Commits {
     }
 }
 
+impl<'a> IntoIterator for &'a Commits {
+    type IntoIter = std::slice::Iter<'a, Commit>;
+    type Item = &'a Commit;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.commits.iter()
+    }
+}
+
 impl Commits {
-    pub(crate) fn iter(&self) -> std::slice::Iter<'_, Commit> {
+    /// The commits, newest first
+    pub fn iter(&self) -> std::slice::Iter<'_, Commit> {
         self.commits.iter()
     }
+
+    /// Map each commit that arrived through a merge on the mainline (the
+    /// first-parent chain from the head) to that merge. Older merges claim
+    /// their commits first, so a branch that was built on top of another
+    /// branch only claims the commits the other branch didn't bring in.
+    #[must_use]
+    pub fn merged_by(&self) -> BTreeMap<Hash, Hash> {
+        let parents: BTreeMap<&Hash, &[Hash]> = self
+            .commits
+            .iter()
+            .map(|commit| (commit.hash(), commit.parents()))
+            .collect();
+
+        let mut mainline = vec![];
+        let mut next = self.commits.first().map(Commit::hash);
+        while let Some(hash) = next {
+            mainline.push(hash);
+            next = parents.get(hash).and_then(|parents| parents.first());
+        }
+        let on_mainline: BTreeSet<&Hash> = mainline.iter().copied().collect();
+
+        let mut merged_by = BTreeMap::new();
+        for merge in mainline.into_iter().rev() {
+            let mut pending: Vec<&Hash> = parents
+                .get(merge)
+                .map(|parents| parents.iter().skip(1).collect())
+                .unwrap_or_default();
+
+            while let Some(hash) = pending.pop() {
+                if on_mainline.contains(hash) || merged_by.contains_key(hash) {
+                    continue;
+                }
+                merged_by.insert(hash.clone(), merge.clone());
+                pending.extend(parents.get(hash).copied().unwrap_or_default());
+            }
+        }
+
+        merged_by
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use time::OffsetDateTime;
+
+    use crate::model::{author::Author, commit::Commit, commits::Commits, hash::Hash};
+
+    fn commit(hash: &str, parents: &[&str]) -> Commit {
+        Commit::new(
+            hash.into(),
+            parents.iter().copied().map(Hash::from).collect(),
+            OffsetDateTime::now_utc(),
+            Author::new("John Doe", "john@doe.org"),
+            String::new(),
+        )
+    }
+
+    #[test]
+    fn branch_commits_belong_to_the_merge_that_brought_them_in() {
+        // root - main-1 ------- merge-1 ------- merge-2
+        //      \              /        \       /
+        //       feature-1 - feature-2   other-1
+        let commits = Commits::from(vec![
+            commit("merge-2", &["merge-1", "other-1"]),
+            commit("other-1", &["merge-1"]),
+            commit("merge-1", &["main-1", "feature-2"]),
+            commit("feature-2", &["feature-1"]),
+            commit("feature-1", &["root"]),
+            commit("main-1", &["root"]),
+            commit("root", &[]),
+        ]);
+
+        assert_eq!(
+            commits.merged_by(),
+            BTreeMap::from([
+                ("feature-1".into(), "merge-1".into()),
+                ("feature-2".into(), "merge-1".into()),
+                ("other-1".into(), "merge-2".into()),
+            ])
+        );
+    }
+
+    #[test]
+    fn a_linear_history_has_no_merges() {
+        let commits = Commits::from(vec![
+            commit("3", &["2"]),
+            commit("2", &["1"]),
+            commit("1", &[]),
+        ]);
+
+        assert!(commits.merged_by().is_empty());
+    }
+
+    #[test]
+    fn a_branch_built_on_another_branch_only_claims_its_own_commits() {
+        // root -------- merge-1 ------------ merge-2
+        //      \       /                    /
+        //       first-1 ------------ second-1
+        let commits = Commits::from(vec![
+            commit("merge-2", &["merge-1", "second-1"]),
+            commit("merge-1", &["root", "first-1"]),
+            commit("second-1", &["first-1"]),
+            commit("first-1", &["root"]),
+            commit("root", &[]),
+        ]);
+
+        assert_eq!(
+            commits.merged_by(),
+            BTreeMap::from([
+                ("first-1".into(), "merge-1".into()),
+                ("second-1".into(), "merge-2".into()),
+            ])
+        );
+    }
 }
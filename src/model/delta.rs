@@ -1,42 +1,137 @@
-use std::{collections::BTreeSet, ffi::OsStr, path::PathBuf};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    ffi::OsStr,
+    path::PathBuf,
+};
 
 use time::OffsetDateTime;
 
-use crate::model::{changed_file::ChangedFile, hash::Hash};
+use crate::model::{
+    author::Author,
+    change::{Change, Kind},
+    changed_file::ChangedFile,
+    commit::Commit,
+    hash::Hash,
+};
 
+/// The files a commit changed, with what [`Statistics`](crate::Statistics)
+/// needs to know about the commit to group it with others
 #[derive(Debug, Eq, PartialEq, Clone, Ord, PartialOrd)]
 pub struct Delta {
-    changes: BTreeSet<ChangedFile>,
+    changes: BTreeMap<ChangedFile, Change>,
     timestamp: OffsetDateTime,
     hash: Hash,
+    authors: BTreeSet<Author>,
+    message: String,
+    pull_request: Option<Hash>,
 }
 
 impl Delta {
-    pub(crate) fn merge(&self, other: &Self) -> Self {
+    /// The id of the commit
+    #[must_use]
+    pub const fn hash(&self) -> &Hash {
+        &self.hash
+    }
+
+    /// When the commit was made
+    #[must_use]
+    pub const fn timestamp(&self) -> OffsetDateTime {
+        self.timestamp
+    }
+
+    /// Each file the commit changed and how
+    #[must_use]
+    pub const fn changes(&self) -> &BTreeMap<ChangedFile, Change> {
+        &self.changes
+    }
+
+    /// The commit's author and co-authors
+    #[must_use]
+    pub const fn authors(&self) -> &BTreeSet<Author> {
+        &self.authors
+    }
+
+    /// The commit message
+    #[must_use]
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// The merge commit that brought this change into the mainline, if any
+    #[must_use]
+    pub const fn pull_request(&self) -> Option<&Hash> {
+        self.pull_request.as_ref()
+    }
+
+    /// The same delta, brought into the mainline by this merge, as found by
+    /// [`Commits::merged_by`](crate::Commits::merged_by)
+    #[must_use]
+    pub fn in_pull_request(self, merge: Hash) -> Self {
         Self {
-            hash: self.hash().clone(),
-            timestamp: self.timestamp(),
-            changes: self
-                .changes
-                .union(&other.changes)
-                .cloned()
-                .collect::<BTreeSet<_>>(),
+            pull_request: Some(merge),
+            ..self
         }
     }
 
-    pub(crate) const fn hash(&self) -> &Hash {
-        &self.hash
+    /// Leave out the files that changed in one of the given ways
+    #[must_use]
+    pub fn excluding(mut self, kinds: &[Kind]) -> Self {
+        self.changes
+            .retain(|_, change| !kinds.contains(&change.kind()));
+        self
     }
 
-    pub(crate) const fn timestamp(&self) -> OffsetDateTime {
-        self.timestamp
+    /// Leave out the files git considers binary
+    #[must_use]
+    pub fn excluding_binary(mut self) -> Self {
+        self.changes.retain(|_, change| !change.is_binary());
+        self
+    }
+
+    /// Keep the files but not the lines changed in them, for a delta whose
+    /// lines are already counted by others, such as a merge's diff that
+    /// repeats the branch it brought in
+    #[must_use]
+    pub fn without_lines(mut self) -> Self {
+        for change in self.changes.values_mut() {
+            *change = change.without_lines();
+        }
+        self
+    }
+
+    /// Leave out the files that aren't in the given set
+    #[must_use]
+    pub fn only(mut self, files: &BTreeSet<ChangedFile>) -> Self {
+        self.changes.retain(|file, _| files.contains(file));
+        self
     }
 
+    #[cfg(test)]
     pub(crate) fn new(hash: Hash, timestamp: OffsetDateTime, changes: Vec<ChangedFile>) -> Self {
         Self {
-            changes: changes.into_iter().collect(),
+            changes: changes
+                .into_iter()
+                .map(|file| (file, Change::default()))
+                .collect(),
             timestamp,
             hash,
+            authors: BTreeSet::new(),
+            message: String::new(),
+            pull_request: None,
+        }
+    }
+
+    /// The delta of a commit that made these changes, combining any file
+    /// listed more than once
+    #[must_use]
+    pub fn for_commit(commit: &Commit, changes: Vec<(ChangedFile, Change)>) -> Self {
+        Self {
+            changes: combine(BTreeMap::new(), changes),
+            timestamp: commit.timestamp(),
+            hash: commit.hash().clone(),
+            authors: commit.authors().cloned().collect(),
+            message: commit.message().into(),
+            pull_request: None,
         }
     }
 
@@ -45,14 +140,20 @@ impl Delta {
             changes: self
                 .changes
                 .iter()
-                .map(|path| path.add_prefix(prefix))
+                .map(|(path, change)| (path.add_prefix(prefix), *change))
                 .collect(),
             timestamp: self.timestamp,
             hash: self.hash.clone(),
+            authors: self.authors.clone(),
+            message: self.message.clone(),
+            pull_request: self.pull_request.clone(),
         }
     }
 
-    pub(crate) fn add_prefix(&self, path: &str) -> Self {
+    /// The same delta with each file prefixed by the name of the repository
+    /// at this path, so files of several repositories can't be confused
+    #[must_use]
+    pub fn add_prefix(&self, path: &str) -> Self {
         self.clone().add_str_prefix(
             PathBuf::from(path)
                 .file_name()
@@ -62,20 +163,41 @@ impl Delta {
     }
 }
 
+/// Add changes to a file-to-change map, combining any file seen twice
+fn combine(
+    mut changes: BTreeMap<ChangedFile, Change>,
+    more: impl IntoIterator<Item = (ChangedFile, Change)>,
+) -> BTreeMap<ChangedFile, Change> {
+    for (file, change) in more {
+        let combined = changes
+            .get(&file)
+            .map_or(change, |existing| existing.combine(change));
+        changes.insert(file, combined);
+    }
+    changes
+}
+
 impl IntoIterator for Delta {
-    type IntoIter = std::collections::btree_set::IntoIter<Self::Item>;
+    type IntoIter = std::collections::btree_map::IntoKeys<ChangedFile, Change>;
     type Item = ChangedFile;
 
     fn into_iter(self) -> Self::IntoIter {
-        self.changes.into_iter()
+        self.changes.into_keys()
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use std::collections::BTreeMap;
+
     use time::OffsetDateTime;
 
-    use crate::model::delta::Delta;
+    use crate::model::{
+        author::Author,
+        change::{Change, Kind},
+        commit::Commit,
+        delta::Delta,
+    };
 
     #[test]
     fn can_put_a_prefix_on_everything_in() {
@@ -94,4 +216,59 @@ mod tests {
             vec!["Something@item 1", "Something@item 2", "Something@item 3"]
         );
     }
+
+    #[test]
+    fn a_file_listed_twice_adds_up_its_churn() {
+        let commit = Commit::new(
+            "1".into(),
+            vec![],
+            OffsetDateTime::now_utc(),
+            Author::new("John Doe", "john@doe.org"),
+            String::new(),
+        );
+        let delta = Delta::for_commit(
+            &commit,
+            vec![
+                ("shared".into(), Change::new(1, 2)),
+                ("first".into(), Change::new(3, 0)),
+                ("shared".into(), Change::new(10, 20)),
+            ],
+        );
+
+        assert_eq!(
+            delta.changes(),
+            &BTreeMap::from([
+                ("first".into(), Change::new(3, 0)),
+                ("shared".into(), Change::new(11, 22)),
+            ])
+        );
+    }
+
+    #[test]
+    fn excluded_kinds_of_change_are_left_out() {
+        let commit = Commit::new(
+            "1".into(),
+            vec![],
+            OffsetDateTime::now_utc(),
+            Author::new("John Doe", "john@doe.org"),
+            String::new(),
+        );
+        let delta = Delta::for_commit(
+            &commit,
+            vec![
+                ("added".into(), Change::default().with_kind(Kind::Added)),
+                ("deleted".into(), Change::default().with_kind(Kind::Deleted)),
+                ("modified".into(), Change::default()),
+            ],
+        );
+
+        assert_eq!(
+            delta
+                .excluding(&[Kind::Added, Kind::Deleted])
+                .into_iter()
+                .map(String::from)
+                .collect::<Vec<_>>(),
+            vec!["modified"]
+        );
+    }
 }
@@ -1,5 +1,4 @@
-use std::convert::TryFrom;
-
+#[cfg(feature = "libgit2")]
 use git2::Oid;
 use time::OffsetDateTime;
 
@@ -9,6 +8,7 @@ impl From<Hash> for String {
     }
 }
 
+/// The id of a commit, or of a group of commits such as a time window
 #[derive(Debug, Clone, Eq, PartialEq, Ord, PartialOrd)]
 pub struct Hash {
     hash: String,
@@ -28,6 +28,7 @@ impl From<String> for Hash {
     }
 }
 
+#[cfg(feature = "libgit2")]
 impl From<Oid> for Hash {
     fn from(oid: Oid) -> Self {
         Self {
@@ -36,6 +37,15 @@ impl From<Oid> for Hash {
     }
 }
 
+#[cfg(feature = "gix")]
+impl From<gix::ObjectId> for Hash {
+    fn from(id: gix::ObjectId) -> Self {
+        Self {
+            hash: id.to_string(),
+        }
+    }
+}
+
 impl From<OffsetDateTime> for Hash {
     fn from(datetime: OffsetDateTime) -> Self {
         Self {
@@ -44,6 +54,7 @@ impl From<OffsetDateTime> for Hash {
     }
 }
 
+#[cfg(feature = "libgit2")]
 impl TryFrom<Hash> for Oid {
     type Error = git2::Error;
 
@@ -52,6 +63,7 @@ impl TryFrom<Hash> for Oid {
     }
 }
 
+#[cfg(feature = "libgit2")]
 impl TryFrom<&Hash> for Oid {
     type Error = git2::Error;
 
@@ -59,3 +71,12 @@ impl TryFrom<&Hash> for Oid {
         Self::from_str(hash.hash.as_str())
     }
 }
+
+#[cfg(feature = "gix")]
+impl TryFrom<&Hash> for gix::ObjectId {
+    type Error = gix::hash::decode::Error;
+
+    fn try_from(hash: &Hash) -> Result<Self, Self::Error> {
+        Self::from_hex(hash.hash.as_bytes())
+    }
+}
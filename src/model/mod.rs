@@ -1,3 +1,5 @@
+pub mod author;
+pub mod change;
 pub mod changed_file;
 pub mod commit;
 pub mod commits;
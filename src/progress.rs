@@ -0,0 +1,210 @@
+use std::{
+    fmt::Write as _,
+    io::{IsTerminal, Write as _},
+    sync::{Arc, Mutex, PoisonError},
+    time::{Duration, Instant},
+};
+
+use git_moves_together::WalkProgress;
+
+/// How often the progress line is redrawn at most
+const REDRAW_EVERY: Duration = Duration::from_millis(100);
+
+/// A single line on stderr showing how far each repository has got, which
+/// does nothing when it is disabled
+#[derive(Debug, Clone, Default)]
+pub struct Progress {
+    board: Option<Arc<Mutex<Board>>>,
+}
+
+impl Progress {
+    /// Show progress unless asked to be quiet or nobody is watching stderr
+    #[must_use]
+    pub fn on_stderr(quiet: bool) -> Self {
+        if quiet || !std::io::stderr().is_terminal() {
+            Self::default()
+        } else {
+            Self {
+                board: Some(Arc::default()),
+            }
+        }
+    }
+
+    /// Add a repository to the line, to be given to it in its
+    /// [`Options`](git_moves_together::Options)
+    #[must_use]
+    pub fn repository(&self, name: &str) -> RepositoryProgress {
+        let index = self.board.as_ref().map_or(0, |board| {
+            let mut board = lock(board);
+            board.repositories.push(Repository::new(name));
+            board.repositories.len() - 1
+        });
+        RepositoryProgress {
+            board: self.board.clone(),
+            index,
+        }
+    }
+
+    /// Clear the line so the results start on an empty one
+    pub fn finish(&self) {
+        if let Some(board) = &self.board {
+            let width = lock(board).width;
+            eprint!("\r{:width$}\r", "");
+        }
+    }
+}
+
+/// The progress of one repository, shared with the walker and the loop that
+/// collects its diffs
+#[derive(Debug, Clone, Default)]
+pub struct RepositoryProgress {
+    board: Option<Arc<Mutex<Board>>>,
+    index: usize,
+}
+
+impl WalkProgress for RepositoryProgress {
+    fn walked(&self) {
+        self.update(|repository| repository.walked += 1, false);
+    }
+}
+
+impl RepositoryProgress {
+    /// The commits have been walked and this many are left to diff
+    pub fn diffing(&self, commits: usize) {
+        self.update(
+            |repository| repository.diffing = Some((commits, Instant::now())),
+            true,
+        );
+    }
+
+    /// Another commit has been diffed
+    pub fn diffed(&self) {
+        self.update(|repository| repository.diffed += 1, false);
+    }
+
+    fn update(&self, change: impl FnOnce(&mut Repository), redraw: bool) {
+        if let Some(board) = &self.board {
+            let mut board = lock(board);
+            change(&mut board.repositories[self.index]);
+            board.draw(redraw);
+        }
+    }
+}
+
+fn lock(board: &Mutex<Board>) -> std::sync::MutexGuard<'_, Board> {
+    board.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+#[derive(Debug, Default)]
+struct Board {
+    repositories: Vec<Repository>,
+    drawn: Option<Instant>,
+    width: usize,
+}
+
+impl Board {
+    fn draw(&mut self, force: bool) {
+        let now = Instant::now();
+        if !force
+            && self
+                .drawn
+                .is_some_and(|drawn| now.duration_since(drawn) < REDRAW_EVERY)
+        {
+            return;
+        }
+        self.drawn = Some(now);
+
+        let line = self.line(now);
+        let width = line.chars().count();
+        let mut stderr = std::io::stderr().lock();
+        let _ = write!(stderr, "\r{line:padding$}", padding = self.width.max(width));
+        let _ = stderr.flush();
+        self.width = width;
+    }
+
+    fn line(&self, now: Instant) -> String {
+        self.repositories
+            .iter()
+            .map(|repository| repository.status(now))
+            .collect::<Vec<_>>()
+            .join(" | ")
+    }
+}
+
+#[derive(Debug)]
+struct Repository {
+    name: String,
+    walked: usize,
+    diffed: usize,
+    diffing: Option<(usize, Instant)>,
+}
+
+impl Repository {
+    fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            walked: 0,
+            diffed: 0,
+            diffing: None,
+        }
+    }
+
+    fn status(&self, now: Instant) -> String {
+        let mut status = format!("{}: {} commits walked", self.name, self.walked);
+        if let Some((total, started)) = self.diffing {
+            let _ = write!(status, ", {}/{total} diffed", self.diffed);
+            if let Some(eta) = eta(self.diffed, total, now.duration_since(started)) {
+                let _ = write!(status, ", ETA {eta}");
+            }
+        }
+        status
+    }
+}
+
+/// Assume the commits left take as long on average as the ones diffed so far
+fn eta(done: usize, total: usize, elapsed: Duration) -> Option<String> {
+    if done == 0 || done >= total {
+        return None;
+    }
+    let left = u32::try_from(total - done).ok()?;
+    let done = u32::try_from(done).ok()?;
+    let seconds = (elapsed * left / done).as_secs();
+    Some(if seconds < 60 {
+        format!("{seconds}s")
+    } else {
+        format!("{}m{:02}s", seconds / 60, seconds % 60)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::{Repository, eta};
+
+    #[test]
+    fn the_eta_assumes_the_remaining_commits_take_as_long_as_the_others() {
+        assert_eq!(eta(0, 10, Duration::from_secs(5)), None);
+        assert_eq!(eta(5, 10, Duration::from_secs(5)), Some("5s".to_string()));
+        assert_eq!(
+            eta(10, 100, Duration::from_secs(10)),
+            Some("1m30s".to_string())
+        );
+        assert_eq!(eta(10, 10, Duration::from_secs(10)), None);
+    }
+
+    #[test]
+    fn a_repository_shows_diffing_once_its_commits_are_walked() {
+        let now = Instant::now();
+        let mut repository = Repository::new("some-repo");
+        repository.walked = 20;
+        assert_eq!(repository.status(now), "some-repo: 20 commits walked");
+
+        repository.diffing = Some((20, now));
+        repository.diffed = 0;
+        assert_eq!(
+            repository.status(now),
+            "some-repo: 20 commits walked, 0/20 diffed"
+        );
+    }
+}
@@ -0,0 +1,540 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs::File,
+    io::BufReader,
+    mem,
+    num::NonZeroUsize,
+    path::PathBuf,
+    sync::Arc,
+};
+
+use futures::future;
+#[cfg(feature = "gix")]
+use git_moves_together::Gix;
+#[cfg(feature = "libgit2")]
+use git_moves_together::LibGit2;
+use git_moves_together::{ChangedFile, Commit, Delta, GitLog, Hash, OnDisk, Options, Repository};
+use time::{Duration, OffsetDateTime};
+use tokio::{sync::mpsc, task};
+
+use crate::{
+    cache::Cache,
+    cli::{Analysis, Backend},
+    cli_errors::Error,
+    filters,
+    progress::{Progress, RepositoryProgress},
+};
+
+/// The repository argument that reads `git log` output from stdin
+const STDIN: &str = "-";
+
+/// How many deltas can wait to be counted before the diffing stops to let
+/// counting catch up, so they don't pile up however long the history is
+const BACKLOG: usize = 1024;
+
+pub async fn read_all_deltas(analysis: Analysis) -> Result<Vec<Delta>, Error> {
+    let mut deltas = vec![];
+    for_each_delta(analysis, |delta| deltas.push(delta)).await?;
+    Ok(deltas)
+}
+
+/// Hand each delta of every repository to `consume` as soon as it has been
+/// read, in no particular order
+pub async fn for_each_delta(
+    analysis: Analysis,
+    mut consume: impl FnMut(Delta),
+) -> Result<(), Error> {
+    let progress = Progress::on_stderr(analysis.quiet);
+    let analysis = Arc::new(analysis);
+    let (sender, mut receiver) = mpsc::channel(BACKLOG);
+    let produce = future::try_join_all(analysis.git_repo.iter().map(|path_str| {
+        read_deltas(
+            analysis.clone(),
+            path_str.clone(),
+            progress.repository(path_str),
+            sender.clone(),
+        )
+    }));
+    drop(sender);
+    let consume = async {
+        while let Some(delta) = receiver.recv().await {
+            consume(delta);
+        }
+        Ok(())
+    };
+
+    let result = tokio::try_join!(produce, consume);
+    progress.finish();
+    result.map(|_| ())
+}
+
+fn options(analysis: &Analysis) -> Options {
+    Options::default()
+        .with_line_counts(analysis.churn)
+        .with_binary_detection(analysis.exclude_binary)
+        .with_rename_detection(analysis.find_renames)
+        .with_recurse_submodules(analysis.recurse_submodules)
+        .with_since(
+            analysis
+                .max_days_ago
+                .and_then(|days| OffsetDateTime::now_utc().checked_sub(Duration::days(days))),
+        )
+}
+
+async fn read_deltas(
+    analysis: Arc<Analysis>,
+    path_str: String,
+    progress: RepositoryProgress,
+    sender: mpsc::Sender<Delta>,
+) -> Result<(), Error> {
+    if analysis.git_log {
+        return read_git_log(analysis, path_str, progress, sender).await;
+    }
+    match analysis.backend {
+        #[cfg(feature = "libgit2")]
+        Backend::Libgit2 => read_repository::<LibGit2>(analysis, path_str, progress, sender).await,
+        #[cfg(feature = "gix")]
+        Backend::Gix => read_repository::<Gix>(analysis, path_str, progress, sender).await,
+    }
+}
+
+/// The commits an analysis looks at, with what it needs to know about the
+/// rest of the history to finish their deltas
+struct Selection {
+    commits: Vec<Commit>,
+    merged_by: BTreeMap<Hash, Hash>,
+    /// Merges compared with their first parent only, whose deltas aren't the
+    /// ones the cache keeps
+    first_parent_only: BTreeSet<Hash>,
+    /// Merges whose first-parent diff repeats the lines of the branch
+    /// commits they brought in, which are counted already
+    repeats_branch: BTreeSet<Hash>,
+    existing: Option<BTreeSet<ChangedFile>>,
+}
+
+impl Selection {
+    fn new(analysis: &Analysis, repo: &impl Repository) -> Result<Self, Error> {
+        let commits = repo.commits_in_current_branch()?;
+        let existing = if analysis.only_existing {
+            Some(repo.files_at_head()?)
+        } else {
+            None
+        };
+        let merged_by = commits.merged_by();
+        let claiming: BTreeSet<&Hash> = merged_by.values().collect();
+        let (mut first_parent_only, mut repeats_branch) = (BTreeSet::new(), BTreeSet::new());
+        let commits = commits
+            .iter()
+            .filter(|commit| filters::within_time_limit(analysis.max_days_ago, commit))
+            .filter(|commit| filters::message_not_excluded(&analysis.exclude_message, commit))
+            .filter(|commit| filters::type_included(&analysis.include_type, commit))
+            .filter(|commit| filters::author_not_excluded(&analysis.exclude_author, commit))
+            .cloned()
+            .map(|commit| {
+                // Against its other parents a merge lists the mainline's work
+                // since the branch started, which isn't part of the request
+                if analysis.by_pull_request && commit.parents().len() > 1 {
+                    first_parent_only.insert(commit.hash().clone());
+                    if claiming.contains(commit.hash()) {
+                        repeats_branch.insert(commit.hash().clone());
+                    }
+                    commit.first_parent_only()
+                } else {
+                    commit
+                }
+            })
+            .collect();
+        Ok(Self {
+            commits,
+            merged_by,
+            first_parent_only,
+            repeats_branch,
+            existing,
+        })
+    }
+
+    fn is_cacheable(&self, hash: &Hash) -> bool {
+        !self.first_parent_only.contains(hash)
+    }
+
+    fn finish(&self, delta: Delta, analysis: &Analysis, name: &str) -> Delta {
+        let merge = self.merged_by.get(delta.hash()).cloned();
+        let delta = narrow(delta, analysis, self.existing.as_ref());
+        let delta = if self.repeats_branch.contains(delta.hash()) {
+            delta.without_lines()
+        } else {
+            delta
+        };
+        match merge {
+            Some(merge) => delta.in_pull_request(merge),
+            None => delta,
+        }
+        .add_prefix(name)
+    }
+}
+
+/// Read history from `git log` output rather than a repository, which
+/// already lists each commit's changes so there is nothing to diff
+async fn read_git_log(
+    analysis: Arc<Analysis>,
+    path_str: String,
+    progress: RepositoryProgress,
+    sender: mpsc::Sender<Delta>,
+) -> Result<(), Error> {
+    let (repo, selection) = {
+        let (analysis, path_str) = (analysis.clone(), path_str.clone());
+        let options = options(&analysis).with_progress(Arc::new(progress.clone()));
+        task::spawn_blocking(move || -> Result<_, Error> {
+            let repo = if path_str == STDIN {
+                GitLog::read(std::io::stdin().lock(), options)?
+            } else {
+                GitLog::read(BufReader::new(File::open(path_str)?), options)?
+            };
+            if !repo.lists_kinds()
+                && let Some(option) = needing_kinds(&analysis)
+            {
+                return Err(Error::KindsUnknown(option));
+            }
+            let selection = Selection::new(&analysis, &repo)?;
+            Ok((repo, selection))
+        })
+        .await??
+    };
+    let name = if path_str == STDIN {
+        "stdin"
+    } else {
+        &path_str
+    };
+
+    progress.diffing(selection.commits.len());
+    for commit in &selection.commits {
+        let delta = repo.compare_with_parent(commit)?;
+        progress.diffed();
+        sender
+            .send(selection.finish(delta, &analysis, name))
+            .await
+            .map_err(|_| Error::Disconnected)?;
+    }
+    Ok(())
+}
+
+/// What the analysis asked for that needs to know how each file changed
+const fn needing_kinds(analysis: &Analysis) -> Option<&'static str> {
+    if analysis.lifecycle {
+        Some("lifecycle")
+    } else if analysis.only_existing {
+        Some("--only-existing")
+    } else if !analysis.exclude_kind.is_empty() {
+        Some("--exclude-kind")
+    } else {
+        None
+    }
+}
+
+async fn read_repository<R: OnDisk>(
+    analysis: Arc<Analysis>,
+    path_str: String,
+    progress: RepositoryProgress,
+    sender: mpsc::Sender<Delta>,
+) -> Result<(), Error> {
+    let (mut selection, mut cache) = {
+        let (analysis, path_str) = (analysis.clone(), path_str.clone());
+        let options = options(&analysis).with_progress(Arc::new(progress.clone()));
+        task::spawn_blocking(move || -> Result<_, Error> {
+            let repo = R::open(PathBuf::from(&path_str), options)?;
+            // What a diff finds inside a submodule changes as its commits are
+            // fetched, so those diffs would go stale
+            let cache = if analysis.no_cache || analysis.recurse_submodules {
+                None
+            } else {
+                Some(Cache::load(
+                    repo.git_dir(),
+                    &repo.options().diff_options(R::BACKEND),
+                ))
+            };
+            Ok((Selection::new(&analysis, &repo)?, cache))
+        })
+        .await??
+    };
+    let commits = mem::take(&mut selection.commits);
+    let finish = |delta: Delta| selection.finish(delta, &analysis, &path_str);
+
+    let mut uncached = vec![];
+    for commit in commits {
+        let cached = match &mut cache {
+            Some(cache) if selection.is_cacheable(commit.hash()) => cache.get(&commit),
+            _ => None,
+        };
+        match cached {
+            Some(delta) => sender
+                .send(finish(delta))
+                .await
+                .map_err(|_| Error::Disconnected)?,
+            None => uncached.push(commit),
+        }
+    }
+
+    progress.diffing(uncached.len());
+    let (fresh_sender, mut fresh) = mpsc::channel(BACKLOG);
+    let workers = compare_in_parallel::<R>(&analysis, &path_str, &uncached, &fresh_sender);
+    drop(fresh_sender);
+    while let Some(delta) = fresh.recv().await {
+        progress.diffed();
+        if let Some(cache) = &mut cache
+            && selection.is_cacheable(delta.hash())
+        {
+            cache.insert(&delta);
+        }
+        sender
+            .send(finish(delta))
+            .await
+            .map_err(|_| Error::Disconnected)?;
+    }
+    for result in future::try_join_all(workers).await? {
+        result?;
+    }
+
+    if let Some(cache) = &cache {
+        cache.save()?;
+    }
+    Ok(())
+}
+
+/// Diff commits on a pool of workers, each with its own handle on the
+/// repository as libgit2 repositories can't be shared between threads
+fn compare_in_parallel<R: OnDisk>(
+    analysis: &Arc<Analysis>,
+    path_str: &str,
+    commits: &[Commit],
+    sender: &mpsc::Sender<Delta>,
+) -> Vec<task::JoinHandle<Result<(), Error>>> {
+    let jobs = analysis
+        .jobs
+        .or_else(|| std::thread::available_parallelism().ok())
+        .map_or(1, NonZeroUsize::get);
+    let chunk_size = commits.len().div_ceil(jobs).max(1);
+    commits
+        .chunks(chunk_size)
+        .map(|chunk| {
+            let (analysis, path_str, chunk, sender) = (
+                analysis.clone(),
+                path_str.to_string(),
+                chunk.to_vec(),
+                sender.clone(),
+            );
+            task::spawn_blocking(move || -> Result<(), Error> {
+                let repo = R::open(PathBuf::from(&path_str), options(&analysis))?;
+                for commit in &chunk {
+                    sender
+                        .blocking_send(repo.compare_with_parent(commit)?)
+                        .map_err(|_| Error::Disconnected)?;
+                }
+                Ok(())
+            })
+        })
+        .collect()
+}
+
+/// Leave out the files the analysis isn't interested in
+fn narrow(delta: Delta, analysis: &Analysis, existing: Option<&BTreeSet<ChangedFile>>) -> Delta {
+    let delta = delta.excluding(&analysis.exclude_kind);
+    let delta = if analysis.exclude_binary {
+        delta.excluding_binary()
+    } else {
+        delta
+    };
+    match existing {
+        Some(files) => delta.only(files),
+        None => delta,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        fs::{self, OpenOptions},
+        io::Write,
+        path::Path,
+        process::Command,
+    };
+
+    use clap::Parser;
+    use git_moves_together::{Delta, GitLog, Statistics, Strategy};
+    use tempfile::tempdir;
+
+    use crate::{
+        cli::{Analysis, Args},
+        cli_errors::Error,
+        read::read_all_deltas,
+    };
+
+    fn git(dir: &Path, args: &[&str]) {
+        let status = Command::new("git")
+            .env("GIT_COMMITTER_NAME", "John Doe")
+            .env("GIT_COMMITTER_EMAIL", "john@doe.org")
+            .env("GIT_AUTHOR_NAME", "John Doe")
+            .env("GIT_AUTHOR_EMAIL", "john@doe.org")
+            .args(args)
+            .current_dir(dir)
+            .status()
+            .unwrap();
+        assert!(status.success(), "git {args:?}");
+    }
+
+    fn git_commit_changing(dir: &Path, files: &[&str]) {
+        for file in files {
+            let mut file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(dir.join(file))
+                .unwrap();
+            writeln!(file, "another line").unwrap();
+        }
+        git(dir, &["add", "--all"]);
+        git(dir, &["commit", "--quiet", "--no-gpg-sign", "-m", "Change"]);
+    }
+
+    /// A branch of its own files, merged after the mainline moved on
+    fn branch_merged_over_mainline_work(dir: &Path) {
+        git(dir, &["init", "--quiet", "--initial-branch", "main"]);
+        git_commit_changing(dir, &["readme"]);
+        git(dir, &["checkout", "--quiet", "-b", "feature"]);
+        for files in [["f1", "g1"], ["f1", "g2"]] {
+            git_commit_changing(dir, &files);
+        }
+        git(dir, &["checkout", "--quiet", "main"]);
+        for files in [["mainline_1"], ["mainline_2"], ["mainline_1"]] {
+            git_commit_changing(dir, &files);
+        }
+        git(
+            dir,
+            &[
+                "merge",
+                "--quiet",
+                "--no-ff",
+                "--no-gpg-sign",
+                "-m",
+                "Merge",
+                "feature",
+            ],
+        );
+    }
+
+    async fn read(args: &[&str], lifecycle: bool) -> Result<Vec<Delta>, Error> {
+        let args =
+            Args::try_parse_from(["git-moves-together", "--quiet"].iter().chain(args)).unwrap();
+        read_all_deltas(Analysis {
+            lifecycle,
+            ..args.analysis
+        })
+        .await
+    }
+
+    async fn deltas(args: &[&str]) -> Vec<Delta> {
+        read(args, false).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn only_a_name_status_log_says_enough_for_what_needs_to_know_how_files_changed() {
+        let (dir, logs) = (tempdir().unwrap(), tempdir().unwrap());
+        branch_merged_over_mainline_work(dir.path());
+
+        for (stat, lists_kinds) in [("--numstat", false), ("--name-status", true)] {
+            let output = Command::new("git")
+                .args(["log", "--diff-merges=separate", "--no-renames", stat])
+                .arg(format!("--format={}", GitLog::FORMAT))
+                .current_dir(dir.path())
+                .output()
+                .unwrap();
+            let log = logs.path().join(stat);
+            fs::write(&log, output.stdout).unwrap();
+            let log = log.to_str().unwrap();
+
+            assert!(read(&["--git-log", log], false).await.is_ok(), "{stat}");
+            for (args, lifecycle, option) in [
+                (&["--only-existing"][..], false, "--only-existing"),
+                (&["--exclude-kind", "added"][..], false, "--exclude-kind"),
+                (&[][..], true, "lifecycle"),
+            ] {
+                let args: Vec<&str> = args.iter().copied().chain(["--git-log", log]).collect();
+                match read(&args, lifecycle).await {
+                    Ok(_) => assert!(lists_kinds, "{stat} {option}"),
+                    Err(Error::KindsUnknown(needing)) => {
+                        assert!(!lists_kinds, "{stat} {option}");
+                        assert_eq!(needing, option);
+                    }
+                    Err(error) => panic!("{error}"),
+                }
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn diffing_on_several_workers_finds_what_one_does() {
+        let dir = tempdir().unwrap();
+        branch_merged_over_mainline_work(dir.path());
+        for files in [["f1", "mainline_1"], ["g2", "readme"], ["f1", "g1"]] {
+            git_commit_changing(dir.path(), &files);
+        }
+        let path = dir.path().to_str().unwrap();
+
+        let sorted = |mut deltas: Vec<Delta>| {
+            deltas.sort_by_key(|delta| delta.hash().clone());
+            deltas
+        };
+        let one = sorted(deltas(&["--no-cache", "--churn", "--jobs", "1", path]).await);
+        let several = sorted(deltas(&["--no-cache", "--churn", "--jobs", "4", path]).await);
+
+        assert_eq!(one.len(), 10);
+        assert_eq!(one, several);
+    }
+
+    #[tokio::test]
+    async fn pull_requests_leave_out_the_mainline_work_they_were_merged_over() {
+        let dir = tempdir().unwrap();
+        branch_merged_over_mainline_work(dir.path());
+        let path = dir.path().to_str().unwrap();
+
+        let mut statistics = Statistics::default();
+        for delta in deltas(&["--by-pull-request", "--churn", path]).await {
+            statistics = statistics.add_delta(&delta, &Strategy::PullRequest);
+        }
+        let coupling = statistics.coupling();
+
+        let pairs: Vec<(String, String)> = coupling
+            .iter()
+            .map(|(key, _)| (key.left().clone().into(), key.right().clone().into()))
+            .collect();
+        assert!(!pairs.is_empty());
+        assert!(
+            pairs
+                .iter()
+                .all(|(left, right)| !left.contains("mainline") && !right.contains("mainline")),
+            "{pairs:?}"
+        );
+
+        // The merge's diff repeats the branch's lines, which count once: f1
+        // gained a line in both branch commits and g1 in one of them
+        let (key, _) = coupling
+            .iter()
+            .find(|(key, _)| {
+                String::from(key.left().clone()).ends_with("@f1")
+                    && String::from(key.right().clone()).ends_with("@g1")
+            })
+            .unwrap();
+        assert_eq!(statistics.churn(key).1, 3);
+
+        // The cache still has the merge compared with both parents
+        let merge = deltas(&["--churn", path])
+            .await
+            .into_iter()
+            .find(|delta| delta.message() == "Merge\n")
+            .unwrap();
+        assert!(
+            merge
+                .changes()
+                .keys()
+                .any(|file| String::from(file.clone()).contains("mainline"))
+        );
+    }
+}
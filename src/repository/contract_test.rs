@@ -1,19 +1,33 @@
-use std::{
-    fs::File,
-    io::Write,
-    os::unix::ffi::OsStringExt,
-    path::{Path, PathBuf},
-    process::Command,
-};
+#[cfg(feature = "libgit2")]
+use std::path::PathBuf;
+use std::{fs::File, io::Write, os::unix::ffi::OsStringExt, path::Path, process::Command};
 
 use rand::prelude::*;
 use tempfile::tempdir;
 
+#[cfg(feature = "libgit2")]
+use crate::repository::libgit2::LibGit2;
+#[cfg(feature = "gix")]
+use crate::repository::{gix::Gix, interface::OnDisk};
 use crate::{
-    model::{commit::Commit, commits::Commits, delta::Delta},
-    repository::{in_memory::InMemory, interface::Repository, libgit2::LibGit2},
+    model::{
+        author::Author,
+        change::{Change, Kind},
+        commit::Commit,
+        commits::Commits,
+        delta::Delta,
+    },
+    repository::{in_memory::InMemory, interface::Repository, options::Options},
 };
 
+fn john_doe() -> Author {
+    Author::new("John Doe", "john@doe.org")
+}
+
+fn added() -> Change {
+    Change::default().with_kind(Kind::Added)
+}
+
 fn in_memory_repository() -> InMemory {
     InMemory::new(
         Commits::from(vec![
@@ -21,36 +35,114 @@ fn in_memory_repository() -> InMemory {
                 "3".into(),
                 vec!["2".into()],
                 time::OffsetDateTime::now_utc(),
+                john_doe(),
+                "Commit message\n".into(),
             ),
             Commit::new(
                 "2".into(),
                 vec!["1".into()],
                 time::OffsetDateTime::now_utc(),
+                john_doe(),
+                "Commit message\n".into(),
+            ),
+            Commit::new(
+                "1".into(),
+                vec![],
+                time::OffsetDateTime::now_utc(),
+                john_doe(),
+                "Commit message\n".into(),
             ),
-            Commit::new("1".into(), vec![], time::OffsetDateTime::now_utc()),
         ]),
         vec![
-            ("1".into(), "file1".into()),
-            ("2".into(), "file2".into()),
-            ("3".into(), "file2".into()),
-            ("3".into(), "file3".into()),
+            ("1".into(), "file1".into(), added()),
+            ("2".into(), "file2".into(), added()),
+            ("3".into(), "file2".into(), Change::default()),
+            ("3".into(), "file3".into(), added()),
         ],
     )
 }
 
-fn libgit2_repository(dir: PathBuf) -> LibGit2 {
-    git_init(&dir);
-    git_add_file(&dir, "file1");
-    git_commit(&dir);
-    git_add_file(&dir, "file2");
-    git_commit(&dir);
-    git_add_file(&dir, "file2");
-    git_add_file(&dir, "file3");
-    git_commit(&dir);
-    LibGit2::new(dir).unwrap()
+fn write_history(dir: &Path) {
+    git_init(dir);
+    git_add_file(dir, "file1");
+    git_commit(dir);
+    git_add_file(dir, "file2");
+    git_commit(dir);
+    git_add_file(dir, "file2");
+    git_add_file(dir, "file3");
+    git_commit(dir);
+}
+
+/// The fixture history written to disk, read by each backend that reads git
+/// repositories
+fn on_disk(dir: &Path) -> Vec<Box<dyn Repository>> {
+    write_history(dir);
+    vec![
+        #[cfg(feature = "libgit2")]
+        Box::from(LibGit2::new(dir.to_path_buf()).unwrap()),
+        #[cfg(feature = "gix")]
+        Box::from(Gix::open(dir.to_path_buf(), Options::default()).unwrap()),
+    ]
+}
+
+/// Every option that changes what a backend reads from a commit
+pub(super) fn everything() -> Options {
+    Options::default()
+        .with_line_counts(true)
+        .with_binary_detection(true)
+        .with_rename_detection(true)
+        .with_recurse_submodules(true)
+}
+
+/// A history whose second commit edits, deletes and moves files, one of
+/// them binary
+pub(super) fn write_changes(dir: &Path) {
+    git_init(dir);
+    git_add_content(dir, "lines", "one\ntwo\n");
+    git_add_content(dir, "image", "\0\u{1}binary\0");
+    git_add_content(dir, "moving", "same content in a new place\n");
+    git_add_content(dir, "doomed", "going away\n");
+    git_commit(dir);
+    git_add_content(dir, "lines", "one\n2\nthree\n");
+    git(dir, &["rm", "--quiet", "doomed"]);
+    git(dir, &["mv", "moving", "moved"]);
+    git_commit_as(dir, "Jane Doe", "jane@doe.org");
+}
+
+/// A project whose second commit moves its `lib` submodule on by a commit,
+/// returning the project's path
+#[cfg(feature = "libgit2")]
+pub(super) fn write_submodule_history(dir: &Path) -> PathBuf {
+    let (library, project) = (dir.join("library"), dir.join("project"));
+    git_init(&library);
+    git_add_content(&library, "inner", "first\n");
+    git_commit(&library);
+    git_init(&project);
+    git_add_content(&project, "outer", "first\n");
+    git(
+        &project,
+        &["submodule", "add", &library.to_string_lossy(), "lib"],
+    );
+    git_commit(&project);
+    git_add_content(&library, "inner", "second\n");
+    git_commit(&library);
+    git(&project.join("lib"), &["pull", "--quiet"]);
+    git_add_content(&project, "outer", "second\n");
+    git(&project, &["add", "lib"]);
+    git_commit(&project);
+    project
+}
+
+pub(super) fn git(dir: &Path, args: &[&str]) {
+    Command::new("git")
+        .args(["-c", "protocol.file.allow=always"])
+        .args(args)
+        .current_dir(dir)
+        .status()
+        .unwrap();
 }
 
-fn git_init(dir: &Path) {
+pub(super) fn git_init(dir: &Path) {
     Command::new("git")
         .arg("init")
         .arg(dir.to_string_lossy().as_ref())
@@ -60,14 +152,18 @@ fn git_init(dir: &Path) {
         .unwrap();
 }
 
-fn git_add_file(dir: &Path, file_name: &str) {
-    let mut file = File::create(dir.join(file_name)).unwrap();
+pub(super) fn git_add_file(dir: &Path, file_name: &str) {
     let random_junk: String = rand::rng()
         .sample_iter(rand::distr::Alphanumeric)
         .take(30)
         .map(char::from)
         .collect();
-    file.write_all(random_junk.as_bytes()).unwrap();
+    git_add_content(dir, file_name, &random_junk);
+}
+
+pub(super) fn git_add_content(dir: &Path, file_name: &str, content: &str) {
+    let mut file = File::create(dir.join(file_name)).unwrap();
+    file.write_all(content.as_bytes()).unwrap();
     file.flush().unwrap();
     Command::new("git")
         .arg("add")
@@ -80,11 +176,15 @@ fn git_add_file(dir: &Path, file_name: &str) {
 }
 
 fn git_commit(dir: &Path) {
+    git_commit_as(dir, "John Doe", "john@doe.org");
+}
+
+pub(super) fn git_commit_as(dir: &Path, name: &str, email: &str) {
     Command::new("git")
-        .env("GIT_COMMITTER_NAME", "John Doe")
-        .env("GIT_COMMITTER_EMAIL", "john@doe.org")
-        .env("GIT_AUTHOR_NAME", "John Doe")
-        .env("GIT_AUTHOR_EMAIL", "john@doe.org")
+        .env("GIT_COMMITTER_NAME", name)
+        .env("GIT_COMMITTER_EMAIL", email)
+        .env("GIT_AUTHOR_NAME", name)
+        .env("GIT_AUTHOR_EMAIL", email)
         .arg("commit")
         .arg("--no-verify")
         .arg("--no-gpg-sign")
@@ -100,10 +200,8 @@ fn git_commit(dir: &Path) {
 #[test]
 fn i_can_get_a_list_of_all_current_commits() {
     let dir = tempdir().unwrap();
-    let repos: Vec<Box<dyn Repository>> = vec![
-        Box::from(libgit2_repository(dir.keep())),
-        Box::from(in_memory_repository()),
-    ];
+    let mut repos = on_disk(&dir.keep());
+    repos.push(Box::from(in_memory_repository()));
     for repo in &repos {
         let actual = repo.commits_in_current_branch().unwrap();
         let mut iter = actual.iter();
@@ -117,14 +215,24 @@ fn i_can_get_a_list_of_all_current_commits() {
                 Commit::new(
                     head.hash().clone(),
                     vec![mid.hash().clone()],
-                    head.timestamp()
+                    head.timestamp(),
+                    john_doe(),
+                    "Commit message\n".into(),
                 ),
                 Commit::new(
                     mid.hash().clone(),
                     vec![root.hash().clone()],
-                    mid.timestamp()
+                    mid.timestamp(),
+                    john_doe(),
+                    "Commit message\n".into(),
+                ),
+                Commit::new(
+                    root.hash().clone(),
+                    vec![],
+                    root.timestamp(),
+                    john_doe(),
+                    "Commit message\n".into(),
                 ),
-                Commit::new(root.hash().clone(), vec![], root.timestamp()),
             ]),
             actual
         );
@@ -135,10 +243,8 @@ fn i_can_get_a_list_of_all_current_commits() {
 fn given_a_commit_i_can_find_out_what_files_changed_in_it() {
     let tempdir = tempdir().unwrap();
     let path = tempdir.path();
-    let repos: Vec<Box<dyn Repository>> = vec![
-        Box::from(in_memory_repository()),
-        Box::from(libgit2_repository(path.to_path_buf())),
-    ];
+    let mut repos = on_disk(path);
+    repos.push(Box::from(in_memory_repository()));
     for repo in &repos {
         let actual = repo.commits_in_current_branch().unwrap();
         let mut iter = actual.iter();
@@ -147,20 +253,21 @@ fn given_a_commit_i_can_find_out_what_files_changed_in_it() {
         iter.next().unwrap();
 
         assert!(iter.next().is_none());
-        let expected: Delta = Delta::new(
+        let commit = Commit::new(
             head.hash().clone(),
+            vec![mid.hash().clone()],
             head.timestamp(),
-            vec!["file2".into(), "file3".into()],
+            john_doe(),
+            "Commit message\n".into(),
         );
-        assert_eq!(
-            expected,
-            repo.compare_with_parent(&Commit::new(
-                head.hash().clone(),
-                vec![mid.hash().clone()],
-                head.timestamp(),
-            ))
-            .unwrap()
+        let expected: Delta = Delta::for_commit(
+            &commit,
+            vec![
+                ("file2".into(), Change::default()),
+                ("file3".into(), added()),
+            ],
         );
+        assert_eq!(expected, repo.compare_with_parent(&commit).unwrap());
     }
 
     tempdir.close().unwrap();
@@ -170,10 +277,8 @@ fn given_a_commit_i_can_find_out_what_files_changed_in_it() {
 fn root_commit_includes_its_added_files() {
     let tempdir = tempdir().unwrap();
     let path = tempdir.path();
-    let repos: Vec<Box<dyn Repository>> = vec![
-        Box::from(in_memory_repository()),
-        Box::from(libgit2_repository(path.to_path_buf())),
-    ];
+    let mut repos = on_disk(path);
+    repos.push(Box::from(in_memory_repository()));
     for repo in &repos {
         let actual = repo.commits_in_current_branch().unwrap();
         let mut iter = actual.iter();
@@ -194,6 +299,26 @@ fn root_commit_includes_its_added_files() {
     tempdir.close().unwrap();
 }
 
+#[test]
+fn i_can_list_the_files_at_head() {
+    let tempdir = tempdir().unwrap();
+    let path = tempdir.path();
+    let mut repos = on_disk(path);
+    repos.push(Box::from(in_memory_repository()));
+    for repo in &repos {
+        assert_eq!(
+            repo.files_at_head()
+                .unwrap()
+                .into_iter()
+                .map(String::from)
+                .collect::<Vec<_>>(),
+            vec!["file1", "file2", "file3"]
+        );
+    }
+
+    tempdir.close().unwrap();
+}
+
 #[test]
 fn git_init_handles_non_utf8_paths() {
     // Create a directory with a non-UTF-8 character
@@ -1,12 +1,32 @@
-use std::sync::{MutexGuard, PoisonError};
-
-use git2::Repository;
 use thiserror::Error;
 
+use crate::repository::git_log::GitLog;
+
+/// Why a repository or log couldn't be read
 #[derive(Error, Debug)]
+#[non_exhaustive]
 pub enum Error {
+    #[cfg(feature = "libgit2")]
+    /// libgit2 failed to read the repository
     #[error("failed to read git repository")]
     LibGit2(#[from] git2::Error),
-    #[error("failed to lock mutex")]
-    MutexPoison(#[from] PoisonError<MutexGuard<'static, Repository>>),
+    #[cfg(feature = "gix")]
+    /// gitoxide failed to read the repository
+    #[error("failed to read git repository")]
+    Gix(#[source] Box<dyn std::error::Error + Send + Sync>),
+    /// The `git log` output couldn't be read
+    #[error("failed to read git log")]
+    Io(#[from] std::io::Error),
+    /// A line of the `git log` output isn't in [`GitLog::FORMAT`]
+    #[error(
+        "line {line} of the git log isn't in the expected format, {problem}; \
+        write it with git log --diff-merges=separate --no-renames --name-status --format='{format}'",
+        format = GitLog::FORMAT
+    )]
+    GitLog {
+        /// The line, counting from one
+        line: usize,
+        /// What was wrong with it
+        problem: String,
+    },
 }
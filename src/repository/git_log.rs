@@ -0,0 +1,729 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    io::BufRead,
+    mem,
+};
+
+use time::OffsetDateTime;
+
+use crate::{
+    model::{
+        author::Author,
+        change::{Change, Kind},
+        changed_file::ChangedFile,
+        commit::Commit,
+        commits::Commits,
+        delta::Delta,
+        hash::Hash,
+    },
+    repository::{errors::Error, interface::Repository, options::Options},
+};
+
+/// Message lines are indented so they can't be mistaken for anything else
+const MESSAGE_INDENT: &str = "    ";
+
+/// The files a commit changed and how
+type FileChanges = Vec<(ChangedFile, Change)>;
+
+/// History read from the output of `git log` rather than from a repository,
+/// so it knows only the files the log lists for each commit
+///
+/// It only knows how they changed when the log was written with
+/// `--name-status`, see [`GitLog::lists_kinds`].
+#[derive(Debug)]
+pub struct GitLog {
+    commits: Commits,
+    /// Each commit's diffs, one for each parent a merge is listed with
+    changes: BTreeMap<Hash, Vec<FileChanges>>,
+    files: BTreeSet<ChangedFile>,
+    /// How the files were listed, if the log listed any
+    listing: Option<Listing>,
+    options: Options,
+}
+
+/// A commit as it is being read, with the line it started on to point at
+/// when it turns out to be incomplete
+#[derive(Default)]
+struct Entry {
+    line: usize,
+    hash: String,
+    parents: Vec<Hash>,
+    timestamp: Option<OffsetDateTime>,
+    author: Option<Author>,
+    message: Vec<String>,
+    changes: FileChanges,
+    /// The changes listed the times before, as a merge is listed once for
+    /// each of its parents
+    diffs: Vec<FileChanges>,
+    /// The old paths of renamed files, which are gone after this commit
+    moved: Vec<ChangedFile>,
+}
+
+/// Where a line is within a commit, which decides what it can be
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Section {
+    /// From the `commit` line to the message
+    Headers,
+    /// The indented message
+    Message,
+    /// The changed files, after the blank line that ends the message
+    Files,
+}
+
+impl Entry {
+    /// Add a listed file, splitting a rename into the deletion and addition
+    /// a repository reports when it isn't looking for renames
+    fn add(
+        &mut self,
+        file: ChangedFile,
+        change: Change,
+        moved: Option<ChangedFile>,
+        options: &Options,
+    ) {
+        match moved {
+            Some(old) if !options.rename_detection => {
+                self.changes
+                    .push((old, Change::new(0, 0).with_kind(Kind::Deleted)));
+                self.changes.push((file, change.with_kind(Kind::Added)));
+            }
+            moved => {
+                self.changes.push((file, change));
+                self.moved.extend(moved);
+            }
+        }
+    }
+
+    fn header(&mut self, line: &str, number: usize) -> Result<(), Error> {
+        if let Some(parents) = parents(line) {
+            self.parents = parents.split_whitespace().map(Hash::from).collect();
+        } else if let Some(date) = line.strip_prefix("date ") {
+            self.timestamp = Some(
+                date.trim()
+                    .parse()
+                    .ok()
+                    .and_then(|seconds| OffsetDateTime::from_unix_timestamp(seconds).ok())
+                    .ok_or_else(|| malformed(number, "date isn't seconds since 1970"))?,
+            );
+        } else if let Some(author) = line.strip_prefix("author ") {
+            self.author = Some(
+                Author::parse(author)
+                    .ok_or_else(|| malformed(number, "author isn't \"Name <email>\""))?,
+            );
+        } else {
+            return Err(malformed(number, "expected a parents, date or author line"));
+        }
+        Ok(())
+    }
+
+    fn commit(mut self) -> Result<(Commit, Vec<FileChanges>, Vec<ChangedFile>), Error> {
+        let missing = |header| malformed(self.line, format!("commit has no {header} line"));
+        let timestamp = self.timestamp.ok_or_else(|| missing("date"))?;
+        let author = self.author.clone().ok_or_else(|| missing("author"))?;
+        let mut message = self.message.join("\n");
+        message.push('\n');
+        self.diffs.push(self.changes);
+
+        Ok((
+            Commit::new(self.hash.into(), self.parents, timestamp, author, message),
+            self.diffs,
+            self.moved,
+        ))
+    }
+}
+
+/// The parents on a `parents` line, which has none for a root commit
+fn parents(line: &str) -> Option<&str> {
+    line.strip_prefix("parents")
+        .filter(|parents| parents.is_empty() || parents.starts_with(' '))
+}
+
+fn malformed(line: usize, problem: impl Into<String>) -> Error {
+    Error::GitLog {
+        line,
+        problem: problem.into(),
+    }
+}
+
+impl GitLog {
+    /// The `git log` format read, followed by one of `--name-only`,
+    /// `--name-status` or `--numstat`. Only `--name-status` says which files
+    /// were added or deleted, see [`GitLog::lists_kinds`].
+    pub const FORMAT: &str = "commit %H%nparents %P%ndate %ct%nauthor %aN <%aE>%n%w(0,4,4)%B";
+
+    /// Read a log written by `git log --format=<FORMAT>`, newest commit first
+    ///
+    /// # Errors
+    ///
+    /// When the log can't be read or isn't in [`GitLog::FORMAT`]
+    pub fn read(input: impl BufRead, options: Options) -> Result<Self, Error> {
+        let mut entries = vec![];
+        let mut entry: Option<Entry> = None;
+        let mut section = Section::Headers;
+        let mut listing = None;
+        let mut lines = input.lines().enumerate().peekable();
+        while let Some((index, line)) = lines.next() {
+            let (line, number) = (line?, index + 1);
+            if let Some(hash) = line.strip_prefix("commit ") {
+                // Among the files a `commit …` line could be a file's name,
+                // unless the parents of a commit follow it
+                let starts_commit = section != Section::Files
+                    || matches!(lines.peek(), Some((_, Ok(next))) if parents(next).is_some());
+                if entry.is_none() || starts_commit {
+                    let hash = hash.trim();
+                    match &mut entry {
+                        // `--diff-merges=separate` repeats a merge for each of
+                        // its parents, and each diff adds to the same commit
+                        Some(entry) if entry.hash == hash => {
+                            entry.message.clear();
+                            entry.diffs.push(mem::take(&mut entry.changes));
+                        }
+                        _ => entries.extend(entry.replace(Entry {
+                            line: number,
+                            hash: hash.to_string(),
+                            ..Entry::default()
+                        })),
+                    }
+                    section = Section::Headers;
+                    continue;
+                }
+            }
+            let Some(entry) = &mut entry else {
+                if line.is_empty() {
+                    continue;
+                }
+                return Err(malformed(number, "expected a commit line first"));
+            };
+
+            if section != Section::Files
+                && let Some(message) = line.strip_prefix(MESSAGE_INDENT)
+            {
+                entry.message.push(message.to_string());
+                section = Section::Message;
+            } else if line.is_empty() {
+                section = Section::Files;
+            } else {
+                match section {
+                    Section::Headers => entry.header(&line, number)?,
+                    Section::Message => {
+                        return Err(malformed(number, "message lines must be indented"));
+                    }
+                    Section::Files => {
+                        let (found, file, change, moved) = changed_file(&line)
+                            .ok_or_else(|| malformed(number, "expected a changed file"))?;
+                        let expected = *listing.get_or_insert(found);
+                        if found != expected {
+                            return Err(malformed(
+                                number,
+                                format!("expected a file listed by {}", expected.flag()),
+                            ));
+                        }
+                        entry.add(file, change, moved, &options);
+                    }
+                }
+            }
+        }
+        entries.extend(entry);
+
+        let entries = entries
+            .into_iter()
+            .map(Entry::commit)
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self::from_entries(entries, listing, options))
+    }
+
+    /// Whether the log says how each file changed, which only a log written
+    /// with `--name-status` does. Otherwise every file is modified or
+    /// renamed, so there are no lifecycle events, no additions or deletions
+    /// to leave out, and the files at head still include deleted ones.
+    #[must_use]
+    pub const fn lists_kinds(&self) -> bool {
+        matches!(self.listing, None | Some(Listing::NameStatus))
+    }
+
+    fn from_entries(
+        entries: Vec<(Commit, Vec<FileChanges>, Vec<ChangedFile>)>,
+        listing: Option<Listing>,
+        options: Options,
+    ) -> Self {
+        // Replay the changes oldest first, so the last one for a file wins
+        let mut files = BTreeSet::new();
+        for (_, diffs, moved) in entries.iter().rev() {
+            for file in moved {
+                files.remove(file);
+            }
+            for (file, change) in diffs.iter().flatten() {
+                if change.kind() == Kind::Deleted {
+                    files.remove(file);
+                } else {
+                    files.insert(file.clone());
+                }
+            }
+        }
+
+        let mut commits = vec![];
+        let mut changes = BTreeMap::new();
+        for (commit, diffs, _) in entries {
+            changes.insert(
+                commit.hash().clone(),
+                diffs
+                    .into_iter()
+                    .map(|diff| {
+                        diff.into_iter()
+                            .map(|(file, change)| (file, narrow(change, &options)))
+                            .collect()
+                    })
+                    .collect(),
+            );
+            commits.push(commit);
+        }
+
+        Self {
+            commits: Commits::from(commits),
+            changes,
+            files,
+            listing,
+            options,
+        }
+    }
+}
+
+/// Only keep what a repository would have found out with the same options
+const fn narrow(change: Change, options: &Options) -> Change {
+    let change = if options.line_counts {
+        change
+    } else {
+        Change::new(0, 0)
+            .with_kind(change.kind())
+            .with_binary(change.is_binary())
+    };
+    change.with_binary(options.binary_detection && change.is_binary())
+}
+
+/// How the log lists changed files, which is the same for all of them
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Listing {
+    NameOnly,
+    NameStatus,
+    NumStat,
+}
+
+impl Listing {
+    const fn flag(self) -> &'static str {
+        match self {
+            Self::NameOnly => "--name-only",
+            Self::NameStatus => "--name-status",
+            Self::NumStat => "--numstat",
+        }
+    }
+}
+
+/// A changed file as listed, with the old path of a renamed file
+type Listed = (Listing, ChangedFile, Change, Option<ChangedFile>);
+
+/// Read a line listing a changed file, as written by `--numstat`,
+/// `--name-status` or `--name-only`
+fn changed_file(line: &str) -> Option<Listed> {
+    let fields: Vec<&str> = line.split('\t').collect();
+    match fields[..] {
+        [added, removed, path] if is_count(added) && is_count(removed) => {
+            let lines = |count: &str| count.parse().unwrap_or(0);
+            let change = Change::new(lines(added), lines(removed)).with_binary(added == "-");
+            let path = unquote(path)?;
+            Some(match renamed(&path) {
+                Some((old, new)) => (
+                    Listing::NumStat,
+                    new.into(),
+                    change.with_kind(Kind::Renamed),
+                    Some(old.into()),
+                ),
+                None => (Listing::NumStat, path.into(), change, None),
+            })
+        }
+        [status, path] if is_status(status) => Some((
+            Listing::NameStatus,
+            unquote(path)?.into(),
+            Change::new(0, 0).with_kind(kind(status)),
+            None,
+        )),
+        [status, old, new] if is_status(status) => {
+            let kind = kind(status);
+            let moved = if kind == Kind::Renamed {
+                Some(unquote(old)?.into())
+            } else {
+                None
+            };
+            Some((
+                Listing::NameStatus,
+                unquote(new)?.into(),
+                Change::new(0, 0).with_kind(kind),
+                moved,
+            ))
+        }
+        [path] => Some((
+            Listing::NameOnly,
+            unquote(path)?.into(),
+            Change::new(0, 0),
+            None,
+        )),
+        _ => None,
+    }
+}
+
+/// A path as git writes it, in double quotes with C escapes when it has
+/// unusual characters, unless `core.quotePath` is off
+fn unquote(path: &str) -> Option<String> {
+    let Some(quoted) = path
+        .strip_prefix('"')
+        .and_then(|path| path.strip_suffix('"'))
+    else {
+        return Some(path.to_string());
+    };
+    let mut bytes = vec![];
+    let mut escapes = quoted.bytes();
+    while let Some(byte) = escapes.next() {
+        if byte != b'\\' {
+            bytes.push(byte);
+            continue;
+        }
+        bytes.push(match escapes.next()? {
+            b'a' => 0x07,
+            b'b' => 0x08,
+            b't' => b'\t',
+            b'n' => b'\n',
+            b'v' => 0x0b,
+            b'f' => 0x0c,
+            b'r' => b'\r',
+            first @ b'0'..=b'3' => {
+                let mut octal = first - b'0';
+                for _ in 0..2 {
+                    let digit = escapes
+                        .next()
+                        .filter(|digit| (b'0'..=b'7').contains(digit))?;
+                    octal = octal * 8 + (digit - b'0');
+                }
+                octal
+            }
+            quoted @ (b'"' | b'\\') => quoted,
+            _ => return None,
+        });
+    }
+    String::from_utf8(bytes).ok()
+}
+
+fn is_count(field: &str) -> bool {
+    field == "-" || (!field.is_empty() && field.bytes().all(|byte| byte.is_ascii_digit()))
+}
+
+/// A status letter, followed by a similarity score for renames and copies
+fn is_status(field: &str) -> bool {
+    let mut bytes = field.bytes();
+    bytes
+        .next()
+        .is_some_and(|byte| b"ABCDMRTUX".contains(&byte))
+        && bytes.all(|byte| byte.is_ascii_digit())
+}
+
+fn kind(status: &str) -> Kind {
+    match status.as_bytes()[0] {
+        b'A' | b'C' => Kind::Added,
+        b'D' => Kind::Deleted,
+        b'R' => Kind::Renamed,
+        _ => Kind::Modified,
+    }
+}
+
+/// The old and new paths of a rename written by `--numstat`, either as
+/// `old => new` or with the part they share outside braces, as in
+/// `src/{old => new}/file`
+fn renamed(path: &str) -> Option<(String, String)> {
+    if let (Some(open), Some(close)) = (path.find('{'), path.rfind('}')) {
+        let (prefix, suffix) = (&path[..open], &path[close + 1..]);
+        let (old, new) = path[open + 1..close].split_once(" => ")?;
+        let join = |middle: &str| format!("{prefix}{middle}{suffix}").replace("//", "/");
+        return Some((join(old), join(new)));
+    }
+    path.split_once(" => ")
+        .map(|(old, new)| (old.to_string(), new.to_string()))
+}
+
+impl Repository for GitLog {
+    fn commits_in_current_branch(&self) -> Result<Commits, Error> {
+        self.options
+            .walk(self.commits.iter().cloned().map(Ok::<_, Error>))
+    }
+
+    fn compare_with_parent(&self, commit: &Commit) -> Result<Delta, Error> {
+        // Only the diffs with the parents the commit still has, as a merge
+        // can be compared with its first parent only
+        let diffs = self
+            .changes
+            .get(commit.hash())
+            .map_or(&[][..], Vec::as_slice);
+        Ok(Delta::for_commit(
+            commit,
+            diffs
+                .iter()
+                .take(commit.parents().len().max(1))
+                .flatten()
+                .cloned()
+                .collect(),
+        ))
+    }
+
+    fn files_at_head(&self) -> Result<BTreeSet<ChangedFile>, Error> {
+        Ok(self.files.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::BTreeSet, path::Path, process::Command};
+
+    use tempfile::tempdir;
+
+    use super::GitLog;
+    #[cfg(feature = "libgit2")]
+    use crate::repository::{
+        contract_test::{git_add_content, git_commit_as, git_init},
+        interface::OnDisk,
+        libgit2::LibGit2,
+    };
+    use crate::{
+        model::{
+            change::{Change, Kind},
+            changed_file::ChangedFile,
+        },
+        repository::{
+            contract_test::{everything, write_changes},
+            errors::Error,
+            interface::Repository,
+            options::Options,
+        },
+    };
+
+    fn git_log(dir: &Path, stat: &str) -> Vec<u8> {
+        Command::new("git")
+            .args(["log", stat, &format!("--format={}", GitLog::FORMAT)])
+            .current_dir(dir)
+            .output()
+            .unwrap()
+            .stdout
+    }
+
+    #[cfg(feature = "libgit2")]
+    #[test]
+    fn commits_and_files_are_read_the_same_as_from_the_repository() {
+        let dir = tempdir().unwrap();
+        write_changes(dir.path());
+        let repository = LibGit2::open(dir.path().to_path_buf(), Options::default()).unwrap();
+        let log = GitLog::read(
+            git_log(dir.path(), "--name-status").as_slice(),
+            Options::default(),
+        )
+        .unwrap();
+
+        let commits = log.commits_in_current_branch().unwrap();
+        assert_eq!(commits, repository.commits_in_current_branch().unwrap());
+        for commit in &commits {
+            assert_eq!(
+                log.compare_with_parent(commit).unwrap(),
+                repository.compare_with_parent(commit).unwrap()
+            );
+        }
+        assert_eq!(
+            log.files_at_head().unwrap(),
+            repository.files_at_head().unwrap()
+        );
+    }
+
+    #[cfg(feature = "libgit2")]
+    #[test]
+    fn files_named_like_headers_are_files() {
+        let dir = tempdir().unwrap();
+        git_init(dir.path());
+        for content in ["one\n", "two\n"] {
+            for file in ["parents.md", "commit x", "date y", "author z", "x"] {
+                git_add_content(dir.path(), file, content);
+            }
+            git_commit_as(dir.path(), "John Doe", "john@doe.org");
+        }
+        let repository = LibGit2::open(dir.path().to_path_buf(), Options::default()).unwrap();
+
+        for stat in ["--name-only", "--name-status"] {
+            let log =
+                GitLog::read(git_log(dir.path(), stat).as_slice(), Options::default()).unwrap();
+            let commits = log.commits_in_current_branch().unwrap();
+            assert_eq!(commits, repository.commits_in_current_branch().unwrap());
+            for commit in &commits {
+                assert_eq!(log.changes[commit.hash()].concat().len(), 5, "{stat}");
+            }
+            assert_eq!(
+                log.files_at_head().unwrap(),
+                repository.files_at_head().unwrap()
+            );
+        }
+    }
+
+    #[cfg(feature = "libgit2")]
+    #[test]
+    fn quoted_paths_are_unquoted() {
+        let dir = tempdir().unwrap();
+        git_init(dir.path());
+        git_add_content(dir.path(), "café", "one\n");
+        git_add_content(dir.path(), "tab\there", "one\n");
+        git_commit_as(dir.path(), "John Doe", "john@doe.org");
+        let repository = LibGit2::open(dir.path().to_path_buf(), Options::default()).unwrap();
+
+        for stat in ["--name-only", "--name-status", "--numstat"] {
+            let log =
+                GitLog::read(git_log(dir.path(), stat).as_slice(), Options::default()).unwrap();
+            assert_eq!(
+                log.files_at_head().unwrap(),
+                repository.files_at_head().unwrap(),
+                "{stat}"
+            );
+        }
+    }
+
+    #[test]
+    fn numstat_gives_line_counts_binary_files_and_renames() {
+        let dir = tempdir().unwrap();
+        write_changes(dir.path());
+        let log = GitLog::read(git_log(dir.path(), "--numstat").as_slice(), everything()).unwrap();
+        let commits = log.commits_in_current_branch().unwrap();
+        let latest = commits.iter().next().unwrap();
+
+        let changes: Vec<(ChangedFile, Change)> = log.changes[latest.hash()].concat();
+        assert_eq!(
+            changes,
+            vec![
+                (ChangedFile::from("doomed"), Change::new(0, 1)),
+                (ChangedFile::from("lines"), Change::new(2, 1)),
+                (
+                    ChangedFile::from("moved"),
+                    Change::new(0, 0).with_kind(Kind::Renamed)
+                ),
+            ]
+        );
+        let first = commits.iter().nth(1).unwrap();
+        assert!(
+            log.changes[first.hash()]
+                .concat()
+                .iter()
+                .any(|(file, change)| *file == ChangedFile::from("image") && change.is_binary())
+        );
+        assert!(!log.files_at_head().unwrap().contains(&"moving".into()));
+    }
+
+    #[test]
+    fn only_name_status_says_how_files_changed() {
+        let dir = tempdir().unwrap();
+        write_changes(dir.path());
+
+        for (stat, lists_kinds) in [
+            ("--name-only", false),
+            ("--name-status", true),
+            ("--numstat", false),
+        ] {
+            let log =
+                GitLog::read(git_log(dir.path(), stat).as_slice(), Options::default()).unwrap();
+            assert_eq!(log.lists_kinds(), lists_kinds, "{stat}");
+        }
+        assert!(
+            GitLog::read(&[][..], Options::default())
+                .unwrap()
+                .lists_kinds()
+        );
+    }
+
+    #[test]
+    fn name_only_lists_files_as_modified() {
+        let log = GitLog::read(
+            "commit abc\nparents\ndate 0\nauthor John Doe <john@doe.org>\n    feat: one\n\nsrc/a\nsrc/b\n"
+                .as_bytes(),
+            Options::default(),
+        )
+        .unwrap();
+        let commits = log.commits_in_current_branch().unwrap();
+        let commit = commits.iter().next().unwrap();
+
+        assert_eq!(commit.message(), "feat: one\n");
+        assert_eq!(
+            log.files_at_head().unwrap(),
+            BTreeSet::from(["src/a".into(), "src/b".into()])
+        );
+    }
+
+    #[test]
+    fn a_merge_listed_once_per_parent_is_one_commit() {
+        let log = GitLog::read(
+            &b"commit abc\nparents d e\ndate 0\nauthor A <a@b.c>\n    merge\n\nA\tb\n\
+               commit abc\nparents d e\ndate 0\nauthor A <a@b.c>\n    merge\n\nM\ta\n"[..],
+            Options::default(),
+        )
+        .unwrap();
+        let commits = log.commits_in_current_branch().unwrap();
+
+        assert_eq!(commits.iter().count(), 1);
+        let merge = commits.iter().next().unwrap();
+        assert_eq!(merge.message(), "merge\n");
+        assert_eq!(log.changes[merge.hash()].concat().len(), 2);
+        let mainline = log
+            .compare_with_parent(&merge.clone().first_parent_only())
+            .unwrap();
+        assert_eq!(
+            mainline.changes().keys().collect::<Vec<_>>(),
+            vec![&ChangedFile::from("b")]
+        );
+    }
+
+    #[test]
+    fn lines_before_the_first_commit_are_rejected() {
+        let result = GitLog::read(&b"src/a\n"[..], Options::default());
+        assert!(matches!(result, Err(Error::GitLog { line: 1, .. })));
+    }
+
+    #[test]
+    fn unindented_message_lines_are_rejected() {
+        let result = GitLog::read(
+            &b"commit abc\nparents\ndate 0\nauthor A <a@b.c>\n    subject\nbody\n"[..],
+            Options::default(),
+        );
+        assert!(matches!(result, Err(Error::GitLog { line: 6, .. })));
+    }
+
+    #[test]
+    fn files_listed_unlike_the_others_are_rejected() {
+        let result = GitLog::read(
+            &b"commit abc\nparents\ndate 0\nauthor A <a@b.c>\n    subject\n\nM\ta\nb\n"[..],
+            Options::default(),
+        );
+        assert!(matches!(result, Err(Error::GitLog { line: 8, .. })));
+    }
+
+    #[test]
+    fn commits_without_a_date_are_rejected() {
+        let result = GitLog::read(
+            "commit abc\nauthor John Doe <john@doe.org>\n\ncommit def\n".as_bytes(),
+            Options::default(),
+        );
+        assert!(matches!(result, Err(Error::GitLog { line: 1, .. })));
+    }
+
+    #[test]
+    fn renames_are_read_from_both_numstat_forms() {
+        assert_eq!(
+            super::renamed("src/{a/f => b}"),
+            Some(("src/a/f".to_string(), "src/b".to_string()))
+        );
+        assert_eq!(
+            super::renamed("src/{ => new}/file"),
+            Some(("src/file".to_string(), "src/new/file".to_string()))
+        );
+        assert_eq!(
+            super::renamed("top => moved"),
+            Some(("top".to_string(), "moved".to_string()))
+        );
+        assert_eq!(super::renamed("plain"), None);
+    }
+}
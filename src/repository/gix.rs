@@ -0,0 +1,361 @@
+use std::{
+    collections::BTreeSet,
+    path::{Path, PathBuf},
+};
+
+use gix::{
+    ObjectId, Tree,
+    bstr::BStr,
+    diff::{Rewrites, blob::Platform},
+    object::tree::diff::{Action, Change as TreeChange},
+    objs::tree::EntryMode,
+    revision::walk::Sorting,
+    traverse::commit::simple::CommitTimeOrder,
+};
+
+use crate::{
+    model::{
+        change::{Change, Kind},
+        changed_file::ChangedFile,
+        commit::Commit,
+        commits::Commits,
+        delta::Delta,
+    },
+    repository::{
+        errors::Error,
+        interface::{OnDisk, Repository},
+        options::Options,
+    },
+};
+
+/// How much memory each handle on a repository may use to keep objects it
+/// has read, as diffing neighbouring commits reads many of the same trees
+const OBJECT_CACHE: usize = 4 * 1024 * 1024;
+
+/// Reads history with gitoxide, a git implementation in pure Rust
+#[derive(Debug, Clone)]
+pub struct Gix {
+    repo: gix::Repository,
+    options: Options,
+}
+
+fn failed(error: impl Into<Box<dyn std::error::Error + Send + Sync>>) -> Error {
+    Error::Gix(error.into())
+}
+
+impl Gix {
+    fn tree(&self, commit: ObjectId) -> Result<Tree<'_>, Error> {
+        self.repo
+            .find_commit(commit)
+            .map_err(failed)?
+            .tree()
+            .map_err(failed)
+    }
+
+    fn changes(&self, old: &Tree<'_>, new: &Tree<'_>) -> Result<Vec<(ChangedFile, Change)>, Error> {
+        let mut blobs = self
+            .repo
+            .diff_resource_cache_for_tree_diff()
+            .map_err(failed)?;
+        let mut changes = vec![];
+        old.changes()
+            .map_err(failed)?
+            .options(|options| {
+                options
+                    .track_path()
+                    .track_rewrites(self.options.rename_detection.then(Rewrites::default));
+            })
+            .for_each_to_obtain_tree(new, |change| {
+                if !change.entry_mode().is_tree() {
+                    changes.extend(self.change(change, &mut blobs)?);
+                }
+                Ok::<_, Error>(Action::Continue)
+            })
+            .map_err(failed)?;
+        Ok(changes)
+    }
+
+    fn change(
+        &self,
+        change: TreeChange<'_, '_, '_>,
+        blobs: &mut Platform,
+    ) -> Result<Vec<(ChangedFile, Change)>, Error> {
+        let (kind, old, new): (Kind, Option<(EntryMode, ObjectId)>, _) = match change {
+            TreeChange::Addition { entry_mode, id, .. } => {
+                (Kind::Added, None, Some((entry_mode, id.detach())))
+            }
+            TreeChange::Deletion { entry_mode, id, .. } => {
+                (Kind::Deleted, Some((entry_mode, id.detach())), None)
+            }
+            TreeChange::Modification {
+                previous_entry_mode,
+                previous_id,
+                entry_mode,
+                id,
+                ..
+            } => (
+                Kind::Modified,
+                Some((previous_entry_mode, previous_id.detach())),
+                Some((entry_mode, id.detach())),
+            ),
+            TreeChange::Rewrite {
+                source_entry_mode,
+                source_id,
+                entry_mode,
+                id,
+                copy,
+                ..
+            } => (
+                if copy { Kind::Added } else { Kind::Renamed },
+                Some((source_entry_mode, source_id.detach())),
+                Some((entry_mode, id.detach())),
+            ),
+        };
+        let path = change.location().to_string();
+
+        if [old, new]
+            .iter()
+            .flatten()
+            .any(|(mode, _)| mode.is_commit())
+        {
+            if self.options.recurse_submodules
+                && let Some(changes) =
+                    self.submodule_changes(&path, old.map(|(_, id)| id), new.map(|(_, id)| id))?
+            {
+                return Ok(changes);
+            }
+            // libgit2 counts the line naming the submodule's commit
+            let (added, removed) = if self.options.line_counts {
+                (usize::from(new.is_some()), usize::from(old.is_some()))
+            } else {
+                (0, 0)
+            };
+            return Ok(vec![(
+                path.into(),
+                Change::new(added, removed).with_kind(kind),
+            )]);
+        }
+
+        let (binary, added, removed) = if self.options.line_counts || self.options.binary_detection
+        {
+            let counts = change
+                .diff(blobs)
+                .map_err(failed)?
+                .line_counts()
+                .map_err(failed)?;
+            blobs.clear_resource_cache_keep_allocation();
+            counts.map_or((true, 0, 0), |counts| {
+                (false, counts.insertions, counts.removals)
+            })
+        } else {
+            (false, 0, 0)
+        };
+        let (added, removed) = if self.options.line_counts {
+            (added as usize, removed as usize)
+        } else {
+            (0, 0)
+        };
+        Ok(vec![(
+            path.into(),
+            Change::new(added, removed)
+                .with_kind(kind)
+                .with_binary(binary),
+        )])
+    }
+
+    /// The files changed inside the submodule at this path between two of its
+    /// commits, or `None` when it isn't checked out or lacks either commit
+    fn submodule_changes(
+        &self,
+        path: &str,
+        old: Option<ObjectId>,
+        new: Option<ObjectId>,
+    ) -> Result<Option<Vec<(ChangedFile, Change)>>, Error> {
+        let Some(submodule) = self.submodule(path) else {
+            return Ok(None);
+        };
+        let tree = |commit: Option<ObjectId>| {
+            commit.map_or_else(
+                || Ok(submodule.repo.empty_tree()),
+                |commit| submodule.tree(commit),
+            )
+        };
+        let (Ok(old), Ok(new)) = (tree(old), tree(new)) else {
+            return Ok(None);
+        };
+
+        Ok(Some(
+            submodule
+                .changes(&old, &new)?
+                .into_iter()
+                .map(|(file, change)| (file.in_directory(path), change))
+                .collect(),
+        ))
+    }
+
+    /// The submodule checked out at this path, if there is one
+    fn submodule(&self, path: &str) -> Option<Self> {
+        let repo = self
+            .repo
+            .submodules()
+            .ok()??
+            .find(|submodule| {
+                submodule
+                    .path()
+                    .is_ok_and(|submodule| submodule.as_ref() == BStr::new(path))
+            })?
+            .open()
+            .ok()??;
+        Some(Self {
+            repo,
+            options: self.options.clone(),
+        })
+    }
+
+    /// The files in a commit, or `None` when it isn't available locally
+    fn files_at(&self, commit: ObjectId) -> Result<Option<BTreeSet<ChangedFile>>, Error> {
+        let Ok(tree) = self.tree(commit) else {
+            return Ok(None);
+        };
+        self.files_in(&tree).map(Some)
+    }
+
+    fn files_in(&self, tree: &Tree<'_>) -> Result<BTreeSet<ChangedFile>, Error> {
+        let mut files = BTreeSet::new();
+        for entry in tree.traverse().breadthfirst.files().map_err(failed)? {
+            let path = entry.filepath.to_string();
+            if entry.mode.is_tree() {
+                continue;
+            }
+            if !(entry.mode.is_commit() && self.options.recurse_submodules) {
+                files.insert(ChangedFile::from(path));
+                continue;
+            }
+
+            let inside = match self.submodule(&path) {
+                Some(submodule) => submodule.files_at(entry.oid)?,
+                None => None,
+            };
+            match inside {
+                Some(inside) => {
+                    files.extend(inside.iter().map(|file| file.in_directory(&path)));
+                }
+                None => {
+                    files.insert(ChangedFile::from(path));
+                }
+            }
+        }
+        Ok(files)
+    }
+}
+
+impl OnDisk for Gix {
+    const BACKEND: &'static str = "gix";
+
+    fn open(path: PathBuf, options: Options) -> Result<Self, Error> {
+        let mut repo = gix::open(path).map_err(failed)?;
+        repo.object_cache_size_if_unset(OBJECT_CACHE);
+
+        Ok(Self { repo, options })
+    }
+
+    fn git_dir(&self) -> &Path {
+        self.repo.git_dir()
+    }
+
+    fn options(&self) -> &Options {
+        &self.options
+    }
+}
+
+impl Repository for Gix {
+    fn commits_in_current_branch(&self) -> Result<Commits, Error> {
+        let head = self.repo.head_id().map_err(failed)?;
+        let mailmap = self.repo.open_mailmap();
+        let walker = self
+            .repo
+            .rev_walk([head])
+            .sorting(Sorting::ByCommitTime(CommitTimeOrder::NewestFirst))
+            .all()
+            .map_err(failed)?;
+
+        self.options.walk(walker.map(|info| {
+            let commit = info.map_err(failed)?.object().map_err(failed)?;
+            Commit::from_gix(&commit, &mailmap).map_err(failed)
+        }))
+    }
+
+    fn compare_with_parent(&self, commit: &Commit) -> Result<Delta, Error> {
+        let tree = self.tree(commit.hash().try_into().map_err(failed)?)?;
+
+        let changes = if commit.parents().is_empty() {
+            self.changes(&self.repo.empty_tree(), &tree)?
+        } else {
+            let mut changes = vec![];
+            for parent in commit.parents() {
+                let parent = self.tree(parent.try_into().map_err(failed)?)?;
+                changes.extend(self.changes(&parent, &tree)?);
+            }
+            changes
+        };
+
+        Ok(Delta::for_commit(commit, changes))
+    }
+
+    fn files_at_head(&self) -> Result<BTreeSet<ChangedFile>, Error> {
+        self.files_in(&self.repo.head_tree().map_err(failed)?)
+    }
+}
+
+#[cfg(all(test, feature = "libgit2"))]
+mod tests {
+    use std::path::Path;
+
+    use tempfile::tempdir;
+
+    use super::Gix;
+    use crate::{
+        model::delta::Delta,
+        repository::{
+            contract_test::{everything, write_changes, write_submodule_history},
+            interface::{OnDisk, Repository},
+            libgit2::LibGit2,
+        },
+    };
+
+    fn deltas<R: OnDisk>(path: &Path) -> Vec<Delta> {
+        let repository = R::open(path.to_path_buf(), everything()).unwrap();
+        repository
+            .commits_in_current_branch()
+            .unwrap()
+            .iter()
+            .map(|commit| repository.compare_with_parent(commit).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn changes_are_read_the_same_as_with_libgit2() {
+        let dir = tempdir().unwrap();
+        write_changes(dir.path());
+
+        assert_eq!(deltas::<Gix>(dir.path()), deltas::<LibGit2>(dir.path()));
+    }
+
+    #[test]
+    fn submodules_are_read_the_same_as_with_libgit2() {
+        let dir = tempdir().unwrap();
+        let project = write_submodule_history(dir.path());
+
+        assert_eq!(deltas::<Gix>(&project), deltas::<LibGit2>(&project));
+        assert_eq!(
+            Gix::open(project.clone(), everything())
+                .unwrap()
+                .files_at_head()
+                .unwrap(),
+            LibGit2::open(project, everything())
+                .unwrap()
+                .files_at_head()
+                .unwrap()
+        );
+    }
+}
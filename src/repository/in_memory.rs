@@ -1,20 +1,29 @@
+use std::collections::BTreeSet;
+
 use crate::{
     model::{
-        changed_file::ChangedFile, commit::Commit, commits::Commits, delta::Delta, hash::Hash,
+        change::{Change, Kind},
+        changed_file::ChangedFile,
+        commit::Commit,
+        commits::Commits,
+        delta::Delta,
+        hash::Hash,
     },
     repository::{errors::Error, interface::Repository},
 };
 
+/// History that was never in git, such as for tests or history from another
+/// version control system
+#[derive(Debug)]
 pub struct InMemory {
     commits: Commits,
-    changes: Vec<(Hash, ChangedFile)>,
+    changes: Vec<(Hash, ChangedFile, Change)>,
 }
 
 impl InMemory {
-    // Note, this is not actually dead, but rather proof that we can swap out our
-    // git provider
-    #[allow(dead_code)]
-    pub(crate) const fn new(commits: Commits, changes: Vec<(Hash, ChangedFile)>) -> Self {
+    /// Commits and the changes made in each, recorded oldest first
+    #[must_use]
+    pub const fn new(commits: Commits, changes: Vec<(Hash, ChangedFile, Change)>) -> Self {
         Self { commits, changes }
     }
 }
@@ -25,15 +34,14 @@ impl Repository for InMemory {
     }
 
     fn compare_with_parent(&self, commit: &Commit) -> Result<Delta, Error> {
-        Ok(Delta::new(
-            commit.hash().clone(),
-            commit.timestamp(),
+        Ok(Delta::for_commit(
+            commit,
             self.changes
                 .clone()
                 .iter()
-                .filter_map(|(hash, change)| {
+                .filter_map(|(hash, file, change)| {
                     if commit.hash() == hash {
-                        Some(change.clone())
+                        Some((file.clone(), *change))
                     } else {
                         None
                     }
@@ -41,4 +49,17 @@ impl Repository for InMemory {
                 .collect(),
         ))
     }
+
+    fn files_at_head(&self) -> Result<BTreeSet<ChangedFile>, Error> {
+        // Changes are recorded oldest first, so the last one for a file wins
+        let mut files = BTreeSet::new();
+        for (_, file, change) in &self.changes {
+            if change.kind() == Kind::Deleted {
+                files.remove(file);
+            } else {
+                files.insert(file.clone());
+            }
+        }
+        Ok(files)
+    }
 }
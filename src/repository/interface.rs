@@ -1,9 +1,50 @@
+use std::{
+    collections::BTreeSet,
+    path::{Path, PathBuf},
+};
+
 use crate::{
-    model::{commit::Commit, commits::Commits, delta::Delta},
-    repository::errors::Error,
+    model::{changed_file::ChangedFile, commit::Commit, commits::Commits, delta::Delta},
+    repository::{errors::Error, options::Options},
 };
 
+/// Somewhere to read history from
 pub trait Repository {
+    /// The commits reachable from the analysed revision, newest first
+    ///
+    /// # Errors
+    ///
+    /// When the history can't be read
     fn commits_in_current_branch(&self) -> Result<Commits, Error>;
-    fn compare_with_parent(&self, _: &Commit) -> Result<Delta, Error>;
+    /// The files a commit changed compared with its parents, or with an empty
+    /// tree for a root commit
+    ///
+    /// # Errors
+    ///
+    /// When the commit or its parents can't be read or diffed
+    fn compare_with_parent(&self, commit: &Commit) -> Result<Delta, Error>;
+    /// Every file in the tree of the analysed revision
+    ///
+    /// # Errors
+    ///
+    /// When the tree can't be read
+    fn files_at_head(&self) -> Result<BTreeSet<ChangedFile>, Error>;
+}
+
+/// A repository on disk, which each worker diffing commits opens for itself
+/// as a handle on one can't be shared between threads
+pub trait OnDisk: Repository + Sized + 'static {
+    /// The backend's name, as backends can find slightly different changes
+    const BACKEND: &'static str;
+
+    /// Open the repository at or above this path
+    ///
+    /// # Errors
+    ///
+    /// When there is no repository there or it can't be opened
+    fn open(path: PathBuf, options: Options) -> Result<Self, Error>;
+    /// Where git keeps the repository's own files
+    fn git_dir(&self) -> &Path;
+    /// What the repository was opened to find out
+    fn options(&self) -> &Options;
 }
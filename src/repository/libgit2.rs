@@ -1,58 +1,262 @@
-use std::{convert::TryInto, path::PathBuf, sync::Arc};
+use std::{
+    collections::BTreeSet,
+    convert::TryInto,
+    fmt::{Debug, Formatter},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
 
-use git2::{Oid, Repository as LibGit2Repository, Sort, Tree};
+use git2::{
+    Diff, DiffDelta, DiffFindOptions, FileMode, Mailmap, ObjectType, Oid, Patch,
+    Repository as LibGit2Repository, Sort, Tree, TreeWalkMode, TreeWalkResult,
+};
 
 use crate::{
     model::{
-        changed_file::ChangedFile, commit::Commit, commits::Commits, delta::Delta, hash::Hash,
+        change::Change, changed_file::ChangedFile, commit::Commit, commits::Commits, delta::Delta,
+        hash::Hash,
+    },
+    repository::{
+        errors::Error,
+        interface::{OnDisk, Repository},
+        options::Options,
     },
-    repository::{errors::Error, interface::Repository},
 };
 
+/// Reads history with libgit2
 #[derive(Clone)]
 pub struct LibGit2 {
     repo: Arc<LibGit2Repository>,
+    options: Options,
 }
 
 impl LibGit2 {
-    pub(crate) fn new(path: PathBuf) -> Result<Self, Error> {
-        let repo = git2::Repository::open(path)?;
-
-        Ok(Self { repo: repo.into() })
+    /// Open the repository at or above this path to only find out which
+    /// files each commit changed
+    ///
+    /// # Errors
+    ///
+    /// When there is no repository there or it can't be opened
+    pub fn new(path: PathBuf) -> Result<Self, Error> {
+        Self::open(path, Options::default())
     }
 
-    fn diff_with_parent(&self, tree: &Tree<'_>, parent: &Hash) -> Result<Vec<ChangedFile>, Error> {
+    fn diff_with_parent(
+        &self,
+        tree: &Tree<'_>,
+        parent: &Hash,
+    ) -> Result<Vec<(ChangedFile, Change)>, Error> {
         let tree1 = parent
             .try_into()
             .and_then(|oid| self.repo.find_commit(oid))
             .and_then(|commit| commit.tree())?;
-        Ok(self
+        self.changes(
+            self.repo
+                .diff_tree_to_tree(Some(&tree1), tree.into(), None)?,
+        )
+    }
+
+    fn changes(&self, mut diff: Diff<'_>) -> Result<Vec<(ChangedFile, Change)>, Error> {
+        if self.options.rename_detection {
+            diff.find_similar(Some(DiffFindOptions::new().renames(true)))?;
+        }
+        let mut changes = vec![];
+        for (index, delta) in diff.deltas().enumerate() {
+            let gitlink =
+                [delta.old_file().mode(), delta.new_file().mode()].contains(&FileMode::Commit);
+            let submodule_changes = if self.options.recurse_submodules && gitlink {
+                self.submodule_changes(&delta)?
+            } else {
+                None
+            };
+            if let Some(submodule_changes) = submodule_changes {
+                changes.extend(submodule_changes);
+                continue;
+            }
+            changes.push(self.change(&diff, index, delta)?);
+        }
+        Ok(changes)
+    }
+
+    fn change(
+        &self,
+        diff: &Diff<'_>,
+        index: usize,
+        delta: DiffDelta<'_>,
+    ) -> Result<(ChangedFile, Change), Error> {
+        let kind = delta.status().into();
+        let patch = if self.options.line_counts || self.options.binary_detection {
+            Patch::from_diff(diff, index)?
+        } else {
+            None
+        };
+        let binary = patch
+            .as_ref()
+            .is_some_and(|patch| patch.delta().flags().is_binary());
+        let (_, added, removed) = match &patch {
+            Some(patch) if self.options.line_counts => patch.line_stats()?,
+            _ => (0, 0, 0),
+        };
+        Ok((
+            delta.into(),
+            Change::new(added, removed)
+                .with_kind(kind)
+                .with_binary(binary),
+        ))
+    }
+
+    /// The files changed inside a submodule, or `None` when the submodule or
+    /// either of its commits isn't available locally
+    fn submodule_changes(
+        &self,
+        delta: &DiffDelta<'_>,
+    ) -> Result<Option<Vec<(ChangedFile, Change)>>, Error> {
+        let Some(path) = delta
+            .new_file()
+            .path()
+            .or_else(|| delta.old_file().path())
+            .map(|path| path.to_string_lossy().into_owned())
+        else {
+            return Ok(None);
+        };
+        let Some(submodule) = self.submodule(&path) else {
+            return Ok(None);
+        };
+        let tree = |oid: Oid| {
+            if oid.is_zero() {
+                Ok(None)
+            } else {
+                submodule
+                    .repo
+                    .find_commit(oid)
+                    .and_then(|commit| commit.tree())
+                    .map(Some)
+            }
+        };
+        let (Ok(old), Ok(new)) = (tree(delta.old_file().id()), tree(delta.new_file().id())) else {
+            return Ok(None);
+        };
+
+        let diff = submodule
+            .repo
+            .diff_tree_to_tree(old.as_ref(), new.as_ref(), None)?;
+        Ok(Some(
+            submodule
+                .changes(diff)?
+                .into_iter()
+                .map(|(file, change)| (file.in_directory(&path), change))
+                .collect(),
+        ))
+    }
+
+    fn submodule(&self, path: &str) -> Option<Self> {
+        let repo = self
+            .repo
+            .find_submodule(path)
+            .and_then(|submodule| submodule.open())
+            .ok()?;
+        Some(Self {
+            repo: repo.into(),
+            ..self.clone()
+        })
+    }
+
+    /// The files in a commit, or `None` when it isn't available locally
+    fn files_at(&self, commit: Oid) -> Result<Option<BTreeSet<ChangedFile>>, Error> {
+        let Ok(tree) = self
             .repo
-            .clone()
-            .diff_tree_to_tree(Some(&tree1), tree.into(), None)?
-            .deltas()
-            .map(std::convert::Into::into)
-            .collect())
+            .find_commit(commit)
+            .and_then(|commit| commit.tree())
+        else {
+            return Ok(None);
+        };
+        self.files_in(&tree).map(Some)
+    }
+
+    fn files_in(&self, tree: &Tree<'_>) -> Result<BTreeSet<ChangedFile>, Error> {
+        let mut files = BTreeSet::new();
+        let mut submodules = vec![];
+        tree.walk(TreeWalkMode::PreOrder, |root, entry| {
+            let path = format!("{root}{}", String::from_utf8_lossy(entry.name_bytes()));
+            match entry.kind() {
+                Some(ObjectType::Tree) => {}
+                Some(ObjectType::Commit) if self.options.recurse_submodules => {
+                    submodules.push((path, entry.id()));
+                }
+                _ => {
+                    files.insert(ChangedFile::from(path));
+                }
+            }
+            TreeWalkResult::Ok
+        })?;
+
+        for (path, oid) in submodules {
+            let inside = match self.submodule(&path) {
+                Some(submodule) => submodule.files_at(oid)?,
+                None => None,
+            };
+            match inside {
+                Some(inside) => {
+                    files.extend(inside.iter().map(|file| file.in_directory(&path)));
+                }
+                None => {
+                    files.insert(ChangedFile::from(path));
+                }
+            }
+        }
+        Ok(files)
     }
 
-    fn to_commit(&self, commit_oid: Oid) -> Result<Commit, Error> {
+    fn to_commit(&self, commit_oid: Oid, mailmap: &Mailmap) -> Result<Commit, Error> {
         self.repo
             .find_commit(commit_oid)
-            .map(Commit::from)
+            .and_then(|commit| Commit::from_git2(&commit, mailmap))
             .map_err(Error::from)
     }
 }
 
+impl Debug for LibGit2 {
+    fn fmt(&self, formatter: &mut Formatter<'_>) -> std::fmt::Result {
+        formatter
+            .debug_struct("LibGit2")
+            .field("path", &self.repo.path())
+            .field("options", &self.options)
+            .finish()
+    }
+}
+
+impl OnDisk for LibGit2 {
+    const BACKEND: &'static str = "libgit2";
+
+    fn open(path: PathBuf, options: Options) -> Result<Self, Error> {
+        let repo = git2::Repository::open(path)?;
+
+        Ok(Self {
+            repo: repo.into(),
+            options,
+        })
+    }
+
+    fn git_dir(&self) -> &Path {
+        self.repo.path()
+    }
+
+    fn options(&self) -> &Options {
+        &self.options
+    }
+}
+
 impl Repository for LibGit2 {
     fn commits_in_current_branch(&self) -> Result<Commits, Error> {
         let mut walker = self.repo.revwalk()?;
-        walker.set_sorting(Sort::TIME & Sort::TOPOLOGICAL)?;
+        // Any sorting makes libgit2 read the whole history before the first
+        // commit comes out, while unsorted it still goes newest first and
+        // only reads as far as the walk is taken
+        walker.set_sorting(Sort::NONE)?;
         walker.push_head()?;
-
-        walker
-            .map(|oid| self.to_commit(oid?))
-            .collect::<Result<Vec<Commit>, Error>>()
-            .map(Commits::from)
+        let mailmap = self.repo.mailmap()?;
+        self.options
+            .walk(walker.map(|oid| self.to_commit(oid?, &mailmap)))
     }
 
     fn compare_with_parent(&self, commit: &Commit) -> Result<Delta, Error> {
@@ -67,12 +271,7 @@ impl Repository for LibGit2 {
             // Root commit has no parent to diff against — diff against an
             // empty tree so that files added in the initial commit are
             // included in coupling analysis.
-            self.repo
-                .clone()
-                .diff_tree_to_tree(None, Some(&tree), None)?
-                .deltas()
-                .map(std::convert::Into::into)
-                .collect()
+            self.changes(self.repo.diff_tree_to_tree(None, Some(&tree), None)?)?
         } else {
             parents
                 .iter()
@@ -81,17 +280,355 @@ impl Repository for LibGit2 {
                 .unwrap_or_else(|| Ok(vec![]))?
         };
 
-        Ok(Delta::new(
-            commit.hash().clone(),
-            commit.timestamp(),
-            changes,
-        ))
+        Ok(Delta::for_commit(commit, changes))
+    }
+
+    fn files_at_head(&self) -> Result<BTreeSet<ChangedFile>, Error> {
+        self.files_in(&self.repo.head()?.peel_to_tree()?)
     }
 }
 
+type Changes = Vec<(ChangedFile, Change)>;
+
 fn flatten_or_first_err(
-    acc: Result<Vec<ChangedFile>, Error>,
-    item: Result<Vec<ChangedFile>, Error>,
-) -> Result<Vec<ChangedFile>, Error> {
+    acc: Result<Changes, Error>,
+    item: Result<Changes, Error>,
+) -> Result<Changes, Error> {
     acc.and_then(|acc| Ok([acc, item?].concat()))
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::Path, process::Command};
+
+    use tempfile::tempdir;
+    use time::{Duration, OffsetDateTime};
+
+    use crate::{
+        model::{
+            author::Author,
+            change::{Change, Kind},
+            changed_file::ChangedFile,
+            commit::Commit,
+        },
+        repository::{
+            contract_test::{
+                git, git_add_content, git_add_file, git_commit_as, git_init,
+                write_submodule_history,
+            },
+            interface::{OnDisk, Repository},
+            libgit2::LibGit2,
+            options::{Options, SLOP},
+        },
+    };
+
+    #[test]
+    fn authors_are_resolved_through_the_mailmap() {
+        let dir = tempdir().unwrap();
+        let path = dir.path();
+        git_init(path);
+        git_add_file(path, "file1");
+        git_commit_as(path, "John Doe", "john@doe.org");
+        git_add_file(path, "file2");
+        git_commit_as(path, "Johnny", "johnny@home.example");
+        fs::write(
+            path.join(".mailmap"),
+            "John Doe <john@doe.org> <johnny@home.example>\n",
+        )
+        .unwrap();
+
+        let authors: Vec<Author> = LibGit2::new(path.to_path_buf())
+            .unwrap()
+            .commits_in_current_branch()
+            .unwrap()
+            .iter()
+            .flat_map(|commit| commit.authors().cloned().collect::<Vec<_>>())
+            .collect();
+
+        assert_eq!(
+            authors,
+            vec![
+                Author::new("John Doe", "john@doe.org"),
+                Author::new("John Doe", "john@doe.org"),
+            ]
+        );
+    }
+
+    #[test]
+    fn lines_added_and_removed_are_counted_on_request() {
+        let dir = tempdir().unwrap();
+        let path = dir.path();
+        git_init(path);
+        git_add_content(path, "file1", "one\ntwo\nthree\n");
+        git_commit_as(path, "John Doe", "john@doe.org");
+        git_add_content(path, "file1", "one\n2\nthree\nfour\n");
+        git_commit_as(path, "John Doe", "john@doe.org");
+
+        let repository = LibGit2::open(
+            path.to_path_buf(),
+            Options::default().with_line_counts(true),
+        )
+        .unwrap();
+        let commits = repository.commits_in_current_branch().unwrap();
+        let latest = commits.iter().next().unwrap();
+        let delta = repository.compare_with_parent(latest).unwrap();
+
+        assert_eq!(
+            delta.changes().iter().collect::<Vec<_>>(),
+            vec![(&ChangedFile::from("file1"), &Change::new(2, 1))]
+        );
+    }
+
+    /// The kind of each file in a commit that deleted one file and moved
+    /// another
+    fn deleted_and_moved(options: Options) -> Vec<(String, Kind)> {
+        let dir = tempdir().unwrap();
+        let path = dir.path();
+        git_init(path);
+        git_add_content(path, "doomed", "going away\n");
+        git_add_content(path, "moving", "same content in a new place\n");
+        git_commit_as(path, "John Doe", "john@doe.org");
+        git(path, &["rm", "--quiet", "doomed"]);
+        git(path, &["mv", "moving", "moved"]);
+        git_commit_as(path, "John Doe", "john@doe.org");
+
+        let repository = LibGit2::open(path.to_path_buf(), options).unwrap();
+        let commits = repository.commits_in_current_branch().unwrap();
+        let latest = commits.iter().next().unwrap();
+        repository
+            .compare_with_parent(latest)
+            .unwrap()
+            .changes()
+            .iter()
+            .map(|(file, change)| (String::from(file.clone()), change.kind()))
+            .collect()
+    }
+
+    #[test]
+    fn deletions_and_renames_keep_their_kind_and_old_path() {
+        assert_eq!(
+            deleted_and_moved(Options::default().with_rename_detection(true)),
+            vec![
+                ("doomed".to_string(), Kind::Deleted),
+                ("moved".to_string(), Kind::Renamed),
+            ]
+        );
+    }
+
+    #[test]
+    fn without_rename_detection_a_move_is_a_deletion_and_an_addition() {
+        assert_eq!(
+            deleted_and_moved(Options::default()),
+            vec![
+                ("doomed".to_string(), Kind::Deleted),
+                ("moved".to_string(), Kind::Added),
+                ("moving".to_string(), Kind::Deleted),
+            ]
+        );
+    }
+
+    #[test]
+    fn binary_files_are_detected_on_request() {
+        let dir = tempdir().unwrap();
+        let path = dir.path();
+        git_init(path);
+        git_add_content(path, "text", "plain text\n");
+        git_add_content(path, "image", "\0\u{1}\u{2}binary\0");
+        git_commit_as(path, "John Doe", "john@doe.org");
+
+        let repository = LibGit2::open(
+            path.to_path_buf(),
+            Options::default().with_binary_detection(true),
+        )
+        .unwrap();
+        let commits = repository.commits_in_current_branch().unwrap();
+        let root = commits.iter().next().unwrap();
+        let delta = repository.compare_with_parent(root).unwrap();
+
+        assert_eq!(
+            delta
+                .changes()
+                .iter()
+                .map(|(file, change)| (String::from(file.clone()), change.is_binary()))
+                .collect::<Vec<_>>(),
+            vec![("image".to_string(), true), ("text".to_string(), false)]
+        );
+    }
+
+    #[test]
+    fn submodule_changes_are_expanded_into_their_files() {
+        let dir = tempdir().unwrap();
+        let project = write_submodule_history(dir.path());
+
+        let changed = |recurse_submodules: bool| {
+            let repository = LibGit2::open(
+                project.clone(),
+                Options::default().with_recurse_submodules(recurse_submodules),
+            )
+            .unwrap();
+            let commits = repository.commits_in_current_branch().unwrap();
+            let latest = commits.iter().next().unwrap();
+            repository
+                .compare_with_parent(latest)
+                .unwrap()
+                .into_iter()
+                .map(String::from)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(changed(false), vec!["lib", "outer"]);
+        assert_eq!(changed(true), vec!["lib/inner", "outer"]);
+        assert_eq!(
+            LibGit2::open(
+                project.clone(),
+                Options::default().with_recurse_submodules(true),
+            )
+            .unwrap()
+            .files_at_head()
+            .unwrap()
+            .into_iter()
+            .map(String::from)
+            .collect::<Vec<_>>(),
+            vec![".gitmodules", "lib/inner", "outer"]
+        );
+    }
+
+    fn git_commit_days_ago(dir: &Path, days: i64) {
+        git_days_ago(
+            dir,
+            days,
+            &[
+                "commit",
+                "--no-verify",
+                "--no-gpg-sign",
+                "-m",
+                "Commit message",
+            ],
+        );
+    }
+
+    fn git_days_ago(dir: &Path, days: i64, args: &[&str]) {
+        let date = format!(
+            "@{} +0000",
+            (OffsetDateTime::now_utc() - Duration::days(days)).unix_timestamp()
+        );
+        Command::new("git")
+            .env("GIT_COMMITTER_NAME", "John Doe")
+            .env("GIT_COMMITTER_EMAIL", "john@doe.org")
+            .env("GIT_AUTHOR_NAME", "John Doe")
+            .env("GIT_AUTHOR_EMAIL", "john@doe.org")
+            .env("GIT_AUTHOR_DATE", &date)
+            .env("GIT_COMMITTER_DATE", &date)
+            .args(args)
+            .current_dir(dir)
+            .status()
+            .unwrap();
+    }
+
+    #[test]
+    fn the_walk_stops_soon_after_the_cutoff() {
+        let dir = tempdir().unwrap();
+        let path = dir.path();
+        git_init(path);
+        for days in (100..120).rev() {
+            git_add_file(path, "old");
+            git_commit_days_ago(path, days);
+        }
+        git_add_file(path, "within-skew");
+        git_commit_days_ago(path, 30);
+        git_add_file(path, "recent");
+        git_commit_days_ago(path, 1);
+
+        let commits = LibGit2::open(
+            path.to_path_buf(),
+            Options::default().with_since(Some(OffsetDateTime::now_utc() - Duration::days(30))),
+        )
+        .unwrap()
+        .commits_in_current_branch()
+        .unwrap();
+
+        // The first few old commits are read in case their clocks were wrong,
+        // the rest of history isn't
+        assert_eq!(commits.iter().count(), 2 + SLOP - 1);
+
+        // A branch that started long ago and was merged late doesn't hide the
+        // recent commits on the other side of the merge
+        let dir = tempdir().unwrap();
+        let path = dir.path();
+        git_init(path);
+        git_add_file(path, "old");
+        git_commit_days_ago(path, 120);
+        git(path, &["checkout", "--quiet", "-b", "long-lived"]);
+        for days in (90..100).rev() {
+            git_add_file(path, "long-lived");
+            git_commit_days_ago(path, days);
+        }
+        git(path, &["checkout", "--quiet", "-"]);
+        git_add_file(path, "recent");
+        git_commit_days_ago(path, 10);
+        git_days_ago(
+            path,
+            1,
+            &[
+                "merge",
+                "--quiet",
+                "--no-ff",
+                "--no-gpg-sign",
+                "-m",
+                "Merge",
+                "long-lived",
+            ],
+        );
+
+        let commits = LibGit2::open(
+            path.to_path_buf(),
+            Options::default().with_since(Some(OffsetDateTime::now_utc() - Duration::days(30))),
+        )
+        .unwrap()
+        .commits_in_current_branch()
+        .unwrap();
+
+        let recent = OffsetDateTime::now_utc() - Duration::days(30);
+        let walked: Vec<&Commit> = commits.iter().collect();
+        assert_eq!(walked.len(), 2 + SLOP - 1);
+        assert!(
+            walked[..2].iter().all(|commit| commit.timestamp() > recent),
+            "{walked:?}"
+        );
+    }
+
+    #[test]
+    fn history_older_than_the_window_is_never_read() {
+        let dir = tempdir().unwrap();
+        let path = dir.path();
+        git_init(path);
+        for days in (100..120).rev() {
+            git_add_file(path, "old");
+            git_commit_days_ago(path, days);
+        }
+        git_add_file(path, "recent");
+        git_commit_days_ago(path, 1);
+
+        // Past the commits the walk may look at in case their clocks were
+        // wrong, the rest of history is gone
+        let output = Command::new("git")
+            .args(["rev-list", &format!("HEAD~{}", SLOP + 3)])
+            .current_dir(path)
+            .output()
+            .unwrap();
+        for hash in String::from_utf8(output.stdout).unwrap().lines() {
+            let (directory, file) = hash.split_at(2);
+            fs::remove_file(path.join(".git/objects").join(directory).join(file)).unwrap();
+        }
+
+        let commits = LibGit2::open(
+            path.to_path_buf(),
+            Options::default().with_since(Some(OffsetDateTime::now_utc() - Duration::days(30))),
+        )
+        .unwrap()
+        .commits_in_current_branch()
+        .unwrap();
+
+        assert_eq!(commits.iter().count(), 1 + SLOP - 1);
+    }
+}
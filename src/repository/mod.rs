@@ -1,7 +1,12 @@
 pub mod errors;
+pub mod git_log;
+#[cfg(feature = "gix")]
+pub mod gix;
 pub mod in_memory;
 pub mod interface;
+#[cfg(feature = "libgit2")]
 pub mod libgit2;
+pub mod options;
 
 #[cfg(test)]
 mod contract_test;
@@ -0,0 +1,139 @@
+use std::{fmt::Debug, sync::Arc};
+
+use time::{Duration, OffsetDateTime};
+
+use crate::{
+    model::{commit::Commit, commits::Commits},
+    repository::errors::Error,
+};
+
+/// How far a committer's clock may be behind before their commits are
+/// taken to be older than they are
+const CLOCK_SKEW: Duration = Duration::days(1);
+/// How many commits in a row past the cutoff end the walk, in case a branch
+/// merged late was committed on a badly wrong clock
+pub(super) const SLOP: usize = 5;
+
+/// Told about each commit a repository walks, such as to show how far it
+/// has got
+pub trait WalkProgress: Debug + Send + Sync {
+    /// Another commit has been walked
+    fn walked(&self);
+}
+
+/// What to find out about each commit, whichever backend reads it
+///
+/// More options may be added, so start from [`Options::default`] and set
+/// the ones that matter with the `with_` methods.
+#[derive(Debug, Clone, Default)]
+#[non_exhaustive]
+#[allow(clippy::struct_excessive_bools)]
+pub struct Options {
+    /// Count the lines added and removed in each file, which needs a full
+    /// patch rather than just the list of changed paths
+    pub line_counts: bool,
+    /// Find out which files are binary, which like line counts means
+    /// looking at their content
+    pub binary_detection: bool,
+    /// Pair up deleted and added files with similar content as renames,
+    /// rather than reporting a move as an unrelated deletion and addition
+    pub rename_detection: bool,
+    /// Look inside submodules and report the files that changed between the
+    /// old and new submodule commits instead of just the submodule itself
+    pub recurse_submodules: bool,
+    /// Stop walking history once commits are older than this, rather than
+    /// reading every commit only for most of them to be filtered out
+    pub since: Option<OffsetDateTime>,
+    /// Told about each commit walked, if anything is
+    progress: Option<Arc<dyn WalkProgress>>,
+}
+
+impl Options {
+    /// Count the lines added and removed in each file
+    #[must_use]
+    pub const fn with_line_counts(mut self, line_counts: bool) -> Self {
+        self.line_counts = line_counts;
+        self
+    }
+
+    /// Find out which files are binary
+    #[must_use]
+    pub const fn with_binary_detection(mut self, binary_detection: bool) -> Self {
+        self.binary_detection = binary_detection;
+        self
+    }
+
+    /// Pair up deleted and added files with similar content as renames
+    #[must_use]
+    pub const fn with_rename_detection(mut self, rename_detection: bool) -> Self {
+        self.rename_detection = rename_detection;
+        self
+    }
+
+    /// Report the files changed inside submodules
+    #[must_use]
+    pub const fn with_recurse_submodules(mut self, recurse_submodules: bool) -> Self {
+        self.recurse_submodules = recurse_submodules;
+        self
+    }
+
+    /// Stop walking history once commits are older than `since`
+    #[must_use]
+    pub const fn with_since(mut self, since: Option<OffsetDateTime>) -> Self {
+        self.since = since;
+        self
+    }
+
+    /// Tell `progress` about each commit walked
+    #[must_use]
+    pub fn with_progress(mut self, progress: Arc<dyn WalkProgress>) -> Self {
+        self.progress = Some(progress);
+        self
+    }
+
+    /// The backend and the options that change what a diff finds, to tell
+    /// cached diffs apart
+    #[must_use]
+    pub fn diff_options(&self, backend: &str) -> String {
+        [
+            (true, backend),
+            (self.rename_detection, "renames"),
+            (self.line_counts, "lines"),
+            (self.binary_detection, "binary"),
+            (self.recurse_submodules, "submodules"),
+        ]
+        .iter()
+        .filter(|(enabled, _)| *enabled)
+        .map(|(_, option)| *option)
+        .collect::<Vec<_>>()
+        .join("-")
+    }
+
+    /// Collect commits walked newest first, stopping soon after `since`
+    pub(super) fn walk(
+        &self,
+        walker: impl Iterator<Item = Result<Commit, Error>>,
+    ) -> Result<Commits, Error> {
+        let cutoff = self.since.and_then(|since| since.checked_sub(CLOCK_SKEW));
+
+        let mut commits = vec![];
+        let mut past_cutoff = 0;
+        for commit in walker {
+            let commit = commit?;
+            if let Some(progress) = &self.progress {
+                progress.walked();
+            }
+            // Newest first, so everything after a run of old commits is older
+            if cutoff.is_some_and(|cutoff| commit.timestamp() < cutoff) {
+                past_cutoff += 1;
+                if past_cutoff == SLOP {
+                    break;
+                }
+            } else {
+                past_cutoff = 0;
+            }
+            commits.push(commit);
+        }
+        Ok(Commits::from(commits))
+    }
+}
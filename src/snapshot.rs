@@ -0,0 +1,252 @@
+use std::{
+    collections::BTreeMap,
+    fmt::{Display, Formatter},
+    fs::File,
+    io::{BufReader, BufWriter},
+    path::Path,
+};
+
+use comfy_table::{ContentArrangement, Table, modifiers::UTF8_ROUND_CORNERS, presets::UTF8_FULL};
+use serde::{Deserialize, Serialize};
+
+use crate::{errors::Error, statistics::CouplingResult};
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+struct Pair {
+    file_a: String,
+    file_b: String,
+    score: f64,
+    together: usize,
+    commits: usize,
+}
+
+/// The coupling of every pair at one point in time, saved so that a later
+/// analysis can be compared with it
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Snapshot {
+    pairs: Vec<Pair>,
+}
+
+impl Snapshot {
+    /// Read a snapshot written by [`Snapshot::write`]
+    ///
+    /// # Errors
+    ///
+    /// When the file can't be read or isn't a snapshot
+    pub fn read(path: &Path) -> Result<Self, Error> {
+        let file = File::open(path)?;
+        Ok(serde_json::from_reader(BufReader::new(file))?)
+    }
+
+    /// Save the snapshot as JSON
+    ///
+    /// # Errors
+    ///
+    /// When the file can't be written
+    pub fn write(&self, path: &Path) -> Result<(), Error> {
+        let file = File::create(path)?;
+        Ok(serde_json::to_writer_pretty(BufWriter::new(file), self)?)
+    }
+
+    /// The pairs that appeared or disappeared since this snapshot, and those
+    /// whose together percentage moved by more than `min_delta` points
+    #[must_use]
+    pub fn diff(&self, newer: &Self, min_delta: f64) -> SnapshotDiff {
+        let before = self.by_key();
+        let after = newer.by_key();
+
+        let mut changes = vec![];
+        for (key, old) in &before {
+            match after.get(key) {
+                None => changes.push(Change::Disappeared((*old).clone())),
+                Some(new) if (new.score - old.score).abs() * 100.0 > min_delta => {
+                    changes.push(Change::Moved((*old).clone(), (*new).clone()));
+                }
+                Some(_) => {}
+            }
+        }
+        for (key, new) in &after {
+            if !before.contains_key(key) {
+                changes.push(Change::Appeared((*new).clone()));
+            }
+        }
+
+        SnapshotDiff { changes }
+    }
+
+    fn by_key(&self) -> BTreeMap<(&str, &str), &Pair> {
+        self.pairs
+            .iter()
+            .map(|pair| ((pair.file_a.as_str(), pair.file_b.as_str()), pair))
+            .collect()
+    }
+}
+
+impl From<&CouplingResult> for Snapshot {
+    fn from(coupling: &CouplingResult) -> Self {
+        Self {
+            pairs: coupling
+                .iter()
+                .map(|(key, (score, together, commits))| Pair {
+                    file_a: key.left().clone().into(),
+                    file_b: key.right().clone().into(),
+                    score: *score,
+                    together: *together,
+                    commits: *commits,
+                })
+                .collect(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Change {
+    Appeared(Pair),
+    Disappeared(Pair),
+    Moved(Pair, Pair),
+}
+
+impl Change {
+    const fn pair(&self) -> &Pair {
+        match self {
+            Self::Appeared(pair) | Self::Disappeared(pair) | Self::Moved(_, pair) => pair,
+        }
+    }
+}
+
+/// How coupling changed between two snapshots
+#[derive(Debug)]
+pub struct SnapshotDiff {
+    changes: Vec<Change>,
+}
+
+impl SnapshotDiff {
+    /// Whether no pair changed enough to report
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+}
+
+impl Display for SnapshotDiff {
+    fn fmt(&self, formatter: &mut Formatter<'_>) -> std::fmt::Result {
+        let mut changes = self.changes.clone();
+        changes.sort_by(|a, b| {
+            let (a, b) = (a.pair(), b.pair());
+            (&a.file_a, &a.file_b).cmp(&(&b.file_a, &b.file_b))
+        });
+
+        let mut table = Table::new();
+        table
+            .load_preset(UTF8_FULL)
+            .apply_modifier(UTF8_ROUND_CORNERS)
+            .set_content_arrangement(ContentArrangement::Dynamic)
+            .set_header(vec!["Change", "File A", "File B", "Before %", "After %"]);
+        for change in changes {
+            let (label, before, after) = match &change {
+                Change::Appeared(new) => ("Appeared", None, Some(new)),
+                Change::Disappeared(old) => ("Disappeared", Some(old), None),
+                Change::Moved(old, new) if new.score > old.score => {
+                    ("Increased", Some(old), Some(new))
+                }
+                Change::Moved(old, new) => ("Decreased", Some(old), Some(new)),
+            };
+            let pair = change.pair();
+            table.add_row(vec![
+                label.to_string(),
+                pair.file_a.clone(),
+                pair.file_b.clone(),
+                before.map_or_else(|| "-".to_string(), |pair| percentage(pair.score)),
+                after.map_or_else(|| "-".to_string(), |pair| percentage(pair.score)),
+            ]);
+        }
+
+        writeln!(formatter, "{table}")
+    }
+}
+
+fn percentage(score: f64) -> String {
+    format!("{:.2}%", score * 100.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::tempdir;
+    use time::OffsetDateTime;
+
+    use crate::{
+        model::delta::Delta,
+        snapshot::{Change, Pair, Snapshot},
+        statistics::{Statistics, Strategy},
+    };
+
+    fn pair(file_a: &str, file_b: &str, score: f64) -> Pair {
+        Pair {
+            file_a: file_a.into(),
+            file_b: file_b.into(),
+            score,
+            together: 1,
+            commits: 1,
+        }
+    }
+
+    #[test]
+    fn a_snapshot_survives_a_round_trip_to_disk() {
+        let coupling = Statistics::default()
+            .add_delta(
+                &Delta::new(
+                    "1".into(),
+                    OffsetDateTime::now_utc(),
+                    vec!["file_1".into(), "file_2".into()],
+                ),
+                &Strategy::Hash,
+            )
+            .coupling();
+        let snapshot = Snapshot::from(&coupling);
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("snapshot.json");
+
+        snapshot.write(&path).unwrap();
+
+        assert_eq!(Snapshot::read(&path).unwrap(), snapshot);
+        assert_eq!(snapshot.pairs, vec![pair("file_1", "file_2", 1.0)]);
+    }
+
+    #[test]
+    fn diff_reports_appeared_disappeared_and_moved_pairs() {
+        let old = Snapshot {
+            pairs: vec![
+                pair("a", "b", 0.5),
+                pair("a", "c", 0.5),
+                pair("b", "c", 0.5),
+            ],
+        };
+        let new = Snapshot {
+            pairs: vec![
+                pair("a", "b", 0.2),
+                pair("b", "c", 0.52),
+                pair("c", "d", 1.0),
+            ],
+        };
+
+        let diff = old.diff(&new, 5.0);
+
+        assert_eq!(
+            diff.changes,
+            vec![
+                Change::Moved(pair("a", "b", 0.5), pair("a", "b", 0.2)),
+                Change::Disappeared(pair("a", "c", 0.5)),
+                Change::Appeared(pair("c", "d", 1.0)),
+            ]
+        );
+    }
+
+    #[test]
+    fn identical_snapshots_have_no_differences() {
+        let snapshot = Snapshot {
+            pairs: vec![pair("a", "b", 0.5)],
+        };
+
+        assert!(snapshot.diff(&snapshot, 0.0).is_empty());
+    }
+}
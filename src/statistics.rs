@@ -1,14 +1,16 @@
 use std::{
     cmp::Ordering,
-    collections::{BTreeMap, BTreeSet},
+    collections::{BTreeMap, HashMap},
     fmt::{Display, Formatter},
 };
 
 use comfy_table::{ContentArrangement, Table, modifiers::UTF8_ROUND_CORNERS, presets::UTF8_FULL};
+use regex::Regex;
 use time::{Duration, OffsetDateTime};
 
-use crate::model::{changed_file::ChangedFile, delta::Delta, hash::Hash};
+use crate::model::{change::Change, changed_file::ChangedFile, delta::Delta, hash::Hash};
 
+/// A pair of files, in sorted order so that each pair has one key
 #[derive(Eq, PartialEq, Hash, Debug, Ord, PartialOrd, Clone)]
 pub struct Key {
     left: ChangedFile,
@@ -16,7 +18,9 @@ pub struct Key {
 }
 
 impl Key {
-    fn new(left: ChangedFile, right: ChangedFile) -> Self {
+    /// The pair of these two files, whichever order they are given in
+    #[must_use]
+    pub fn new(left: ChangedFile, right: ChangedFile) -> Self {
         let mut keys = [left, right];
         keys.sort();
 
@@ -25,25 +29,141 @@ impl Key {
             right: keys[1].clone(),
         }
     }
+
+    /// The file that sorts first
+    #[must_use]
+    pub const fn left(&self) -> &ChangedFile {
+        &self.left
+    }
+
+    /// The file that sorts last
+    #[must_use]
+    pub const fn right(&self) -> &ChangedFile {
+        &self.right
+    }
 }
 
+/// Every pair of files that changed together, with how strongly they are
+/// coupled, shown as a table strongest first
+#[derive(Debug)]
 pub struct CouplingResult {
     result: Vec<(Key, Calculation)>,
+    churn: Option<BTreeMap<Key, Churn>>,
 }
 
 impl CouplingResult {
-    pub(crate) const fn is_empty(&self) -> bool {
+    /// Whether no two files ever changed together
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
         self.result.is_empty()
     }
+
+    /// Each pair and its coupling, in the order of their keys
+    pub fn iter(&self) -> std::slice::Iter<'_, (Key, Calculation)> {
+        self.result.iter()
+    }
+
+    /// Weigh each pair by the lines changed as well, shown as extra columns
+    #[must_use]
+    pub fn with_churn(mut self, statistics: &Statistics) -> Self {
+        self.churn = Some(
+            self.result
+                .iter()
+                .map(|(key, _)| (key.clone(), statistics.churn(key)))
+                .collect(),
+        );
+        self
+    }
+
+    /// The `count` most strongly coupled pairs, strongest first, weighing
+    /// the together share by how often the pair changed
+    #[must_use]
+    pub fn strongest(&self, count: usize) -> Vec<Key> {
+        let mut coupling = self.result.clone();
+        coupling.sort_by(|a, b| display_order(b, a));
+        coupling
+            .into_iter()
+            .take(count)
+            .map(|(key, _)| key)
+            .collect()
+    }
+}
+
+impl<'a> IntoIterator for &'a CouplingResult {
+    type IntoIter = std::slice::Iter<'a, (Key, Calculation)>;
+    type Item = &'a (Key, Calculation);
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.result.iter()
+    }
+}
+
+/// A changed file's place in the lookup table of `Statistics`, so that
+/// counting only ever stores and compares integers
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Ord, PartialOrd)]
+struct FileId(u32);
+
+/// Hands out one id per distinct file and turns ids back into files
+#[derive(Debug, Default)]
+struct Files {
+    ids: HashMap<ChangedFile, FileId>,
+    files: Vec<ChangedFile>,
+}
+
+impl Files {
+    fn intern(&mut self, file: &ChangedFile) -> FileId {
+        if let Some(id) = self.ids.get(file) {
+            return *id;
+        }
+        let id = FileId(u32::try_from(self.files.len()).expect("More than u32::MAX files"));
+        self.files.push(file.clone());
+        self.ids.insert(file.clone(), id);
+        id
+    }
+
+    fn id(&self, file: &ChangedFile) -> Option<FileId> {
+        self.ids.get(file).copied()
+    }
+
+    fn file(&self, id: FileId) -> &ChangedFile {
+        &self.files[id.0 as usize]
+    }
+}
+
+type Changes = BTreeMap<FileId, Change>;
+
+/// How many grouped deltas something was part of, and how many lines they
+/// changed in it
+#[derive(Debug, Default, Clone, Copy)]
+struct Tally {
+    deltas: usize,
+    lines: usize,
 }
 
-#[derive(Default)]
+/// How often files and pairs of files changed, to find their coupling in.
+///
+/// Counts are kept as deltas arrive, so a delta can be dropped as soon as it
+/// has been added. Deltas can arrive in any order, so with any strategy but
+/// [`Strategy::Hash`] the files of each group are held on to in case a later
+/// delta joins it, which grows with the number of groups rather than with
+/// the deltas in them.
+#[derive(Debug, Default)]
 pub struct Statistics {
-    hash_to_delta: BTreeMap<Hash, Delta>,
-    change_to_delta: BTreeMap<ChangedFile, BTreeSet<Hash>>,
+    files: Files,
+    open_groups: BTreeMap<Hash, Changes>,
+    file_tallies: HashMap<FileId, Tally>,
+    pair_tallies: HashMap<(FileId, FileId), Tally>,
+    sessions: BTreeMap<OffsetDateTime, (OffsetDateTime, Hash)>,
 }
 
-type Calculation = (f64, usize, usize);
+/// How strongly a pair is coupled: the share of the grouped deltas either
+/// file was part of that changed both, how many changed both, and how many
+/// changed either
+pub type Calculation = (f64, usize, usize);
+
+/// The share of the pair's changed lines that changed together, and how many
+/// lines that was
+pub type Churn = (f64, usize);
 
 #[allow(clippy::cast_precision_loss)]
 fn display_order((_, a): &(Key, Calculation), (_, b): &(Key, Calculation)) -> Ordering {
@@ -52,126 +172,316 @@ fn display_order((_, a): &(Key, Calculation), (_, b): &(Key, Calculation)) -> Or
         .unwrap_or(Ordering::Equal)
 }
 
-#[derive(Copy, Clone)]
+/// Which deltas count as one change, so that the files in them changed
+/// together
+#[derive(Clone, Debug)]
+#[non_exhaustive]
 pub enum Strategy {
+    /// Each commit on its own
     Hash,
+    /// The commits in the same fixed window of time, counted from 1970
     CommitTime(Duration),
+    /// Runs of commits that are each no more than this far apart
+    SlidingWindow(Duration),
+    /// The commits by the same authors in the same fixed window of time. The
+    /// set of authors, co-authors included, must match exactly, so a person's
+    /// solo and paired commits are counted apart
+    AuthorTime(Duration),
+    /// The commits whose message mentions the same ticket, using the first
+    /// capture group of the pattern if it has one
+    Ticket(Regex),
+    /// The commits brought into the mainline by the same merge, which needs
+    /// deltas marked with [`Delta::in_pull_request`]
+    PullRequest,
+}
+
+/// The first capture group if the pattern has one, otherwise the whole match.
+fn ticket(pattern: &Regex, message: &str) -> Option<String> {
+    pattern.captures(message).and_then(|captures| {
+        captures
+            .get(1)
+            .or_else(|| captures.get(0))
+            .map(|ticket| format!("ticket {}", ticket.as_str()))
+    })
+}
+
+fn window_start(delta: &Delta, duration: Duration) -> OffsetDateTime {
+    OffsetDateTime::from_unix_timestamp(
+        duration.whole_seconds() * (delta.timestamp().unix_timestamp() / duration.whole_seconds()),
+    )
+    .expect("Timestamp would overflow integer")
 }
 
 impl Statistics {
-    pub(crate) fn add_delta(self, delta: &Delta, strategy: &Strategy) -> Self {
-        let mut hash_to_delta = self.hash_to_delta;
-        let (key, grouped_delta) = match strategy {
-            Strategy::Hash => (delta.hash().clone(), delta.clone()),
+    /// Count a delta, grouping it with earlier ones as the strategy says.
+    /// Every delta of an analysis must be added with the same strategy, in
+    /// any order.
+    ///
+    /// # Panics
+    ///
+    /// When a [`Strategy::CommitTime`] or [`Strategy::AuthorTime`] window is
+    /// shorter than a second
+    #[must_use]
+    pub fn add_delta(mut self, delta: &Delta, strategy: &Strategy) -> Self {
+        let changes = self.intern(delta);
+        let (key, grouped_changes) = match strategy {
+            Strategy::Hash => {
+                // Every commit is its own group, so nothing can join it later
+                self.join(Changes::new(), changes, false);
+                return self;
+            }
             Strategy::CommitTime(duration) => {
-                let key: Hash = OffsetDateTime::from_unix_timestamp(
-                    duration.whole_seconds()
-                        * (delta.timestamp().unix_timestamp() / duration.whole_seconds()),
-                )
-                .expect("Timestamp would overflow integer")
-                .into();
-                (
-                    key.clone(),
-                    hash_to_delta.get(&key).map_or_else(
-                        || delta.clone(),
-                        |existing_delta| existing_delta.merge(delta),
-                    ),
-                )
+                let key: Hash = window_start(delta, *duration).into();
+                self.merge_into(key, changes)
+            }
+            Strategy::AuthorTime(duration) => {
+                let authors = delta
+                    .authors()
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                let key: Hash = format!("{authors} {}", window_start(delta, *duration)).into();
+                self.merge_into(key, changes)
+            }
+            Strategy::PullRequest => {
+                let key = delta.pull_request().unwrap_or_else(|| delta.hash()).clone();
+                self.merge_into(key, changes)
+            }
+            Strategy::SlidingWindow(gap) => self.join_session(delta.timestamp(), changes, *gap),
+            Strategy::Ticket(pattern) => {
+                let key = ticket(pattern, delta.message())
+                    .map_or_else(|| delta.hash().clone(), Hash::from);
+                self.merge_into(key, changes)
             }
         };
-        hash_to_delta.insert(key.clone(), grouped_delta.clone());
 
-        let mut change_to_delta = self.change_to_delta;
-        for change in grouped_delta {
-            let mut coupled_deltas = change_to_delta
-                .get(&change)
-                .map_or_else(BTreeSet::new, Clone::clone);
+        self.open_groups.insert(key, grouped_changes);
 
-            coupled_deltas.insert(key.clone());
-            change_to_delta.insert(change, coupled_deltas);
-        }
+        self
+    }
 
-        Self {
-            hash_to_delta,
-            change_to_delta,
-        }
+    fn intern(&mut self, delta: &Delta) -> Changes {
+        delta
+            .changes()
+            .iter()
+            .map(|(file, change)| (self.files.intern(file), *change))
+            .collect()
     }
 
-    pub(crate) fn coupling(&self) -> CouplingResult {
-        let changes = self.changed_files();
-        CouplingResult {
-            result: changes
-                .iter()
-                .fold(BTreeMap::new(), |total, change| {
-                    self.add_statistic(&changes, total, change)
-                })
-                .into_iter()
-                .collect(),
+    fn merge_into(&mut self, key: Hash, changes: Changes) -> (Hash, Changes) {
+        let existing = self.open_groups.remove(&key).unwrap_or_default();
+        (key, self.join(existing, changes, false))
+    }
+
+    /// Sessions are runs of deltas where each is no more than `gap` from the
+    /// next. A delta can bridge two existing sessions, in which case both are
+    /// removed and replaced with a single merged session.
+    fn join_session(
+        &mut self,
+        timestamp: OffsetDateTime,
+        changes: Changes,
+        gap: Duration,
+    ) -> (Hash, Changes) {
+        let overlapping: Vec<OffsetDateTime> = self
+            .sessions
+            .range(..=timestamp + gap)
+            .rev()
+            .take_while(|(_, (end, _))| *end >= timestamp - gap)
+            .map(|(start, _)| *start)
+            .collect();
+
+        let (mut start, mut end) = (timestamp, timestamp);
+        let mut merged = self.join(Changes::new(), changes, false);
+        for session_start in overlapping {
+            let Some((session_end, session_key)) = self.sessions.remove(&session_start) else {
+                continue;
+            };
+            start = start.min(session_start);
+            end = end.max(session_end);
+
+            if let Some(session) = self.open_groups.remove(&session_key) {
+                merged = self.join(session, merged, true);
+            }
         }
+
+        let key: Hash = start.into();
+        self.sessions.insert(start, (end, key.clone()));
+        (key, merged)
     }
 
-    fn changed_files(&self) -> BTreeSet<ChangedFile> {
-        self.hash_to_delta
-            .values()
-            .flat_map(std::clone::Clone::clone)
-            .collect()
+    /// Count the files of `more` as part of `group` from now on, taking back
+    /// what they were counted as if `more` was already a group of its own.
+    /// Only the pairs with a file of `more` change, so only those are
+    /// tallied again, and two counted groups join through the smaller one.
+    fn join(&mut self, group: Changes, more: Changes, counted: bool) -> Changes {
+        let (group, more) = if counted && more.len() > group.len() {
+            (more, group)
+        } else {
+            (group, more)
+        };
+        let before: Changes = more
+            .keys()
+            .filter_map(|file| group.get(file).map(|change| (*file, *change)))
+            .collect();
+        let joined = combine(group, more.clone());
+
+        // The lines a file was counted with in the group and on its own
+        let in_group = |file: &FileId| {
+            if more.contains_key(file) {
+                before.get(file)
+            } else {
+                joined.get(file)
+            }
+            .map(Change::churn)
+        };
+        let on_own = |file: &FileId| more.get(file).filter(|_| counted).map(Change::churn);
+        let both = |lines: &dyn Fn(&FileId) -> Option<usize>, left, right| {
+            lines(left)
+                .zip(lines(right))
+                .map(|(left, right)| left + right)
+        };
+
+        for file in more.keys() {
+            let change = joined[file];
+            recount(
+                &mut self.file_tallies,
+                *file,
+                [in_group(file), on_own(file)],
+                change.churn(),
+            );
+            for (other, other_change) in &joined {
+                // Pairs within `more` are met from both ends, so take one
+                if other == file || (more.contains_key(other) && other < file) {
+                    continue;
+                }
+                recount(
+                    &mut self.pair_tallies,
+                    (*file.min(other), *file.max(other)),
+                    [both(&in_group, file, other), both(&on_own, file, other)],
+                    change.churn() + other_change.churn(),
+                );
+            }
+        }
+        joined
     }
 
-    fn add_statistic(
-        &self,
-        changes: &BTreeSet<ChangedFile>,
-        total: BTreeMap<Key, Calculation>,
-        change: &ChangedFile,
-    ) -> BTreeMap<Key, Calculation> {
-        changes
+    /// Only pairs that actually changed together have a tally, so this scales
+    /// with how much co-occurs rather than with the square of the files
+    #[allow(clippy::cast_precision_loss)]
+    #[must_use]
+    pub fn coupling(&self) -> CouplingResult {
+        let mut result: Vec<(Key, Calculation)> = self
+            .pair_tallies
             .iter()
-            .filter(|other| &change != other)
-            .map(|other| self.deltas_containing(change, other))
-            .fold(total, |acc, count_and_total| {
-                Self::insert_with_new_coupling_item(acc, count_and_total)
+            .map(|(&(left, right), together)| {
+                let count = together.deltas;
+                let total_changes =
+                    self.file_tally(left).deltas + self.file_tally(right).deltas - count;
+                let score = (count as f64) / (total_changes as f64);
+                (
+                    Key::new(
+                        self.files.file(left).clone(),
+                        self.files.file(right).clone(),
+                    ),
+                    (score, count, total_changes),
+                )
             })
+            .collect();
+        result.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+        CouplingResult {
+            result,
+            churn: None,
+        }
+    }
+
+    /// The coupling of one pair, which is all zero if they never changed
+    #[allow(clippy::cast_precision_loss)]
+    #[must_use]
+    pub fn calculation(&self, key: &Key) -> Calculation {
+        let (left, right, together) = self.tallies(key);
+        let count = together.deltas;
+        let total_changes = left.deltas + right.deltas - count;
+        if total_changes == 0 {
+            (0.0, count, total_changes)
+        } else {
+            (
+                (count as f64) / (total_changes as f64),
+                count,
+                total_changes,
+            )
+        }
     }
 
+    /// Like the together percentage, but each grouped delta counts by the lines
+    /// it changed in either file rather than once, so a one line touch barely
+    /// moves the score while a large rewrite of both files dominates it
     #[allow(clippy::cast_precision_loss)]
-    fn insert_with_new_coupling_item(
-        acc: BTreeMap<Key, Calculation>,
-        (coupling_key, count, total_changes): (Key, usize, usize),
-    ) -> BTreeMap<Key, Calculation> {
-        let mut new = acc;
-        let score = (count as f64) / (total_changes as f64);
-
-        if score > 0.0 {
-            new.insert(coupling_key, (score, count, total_changes));
+    #[must_use]
+    pub fn churn(&self, key: &Key) -> Churn {
+        let (left, right, together) = self.tallies(key);
+        let total = left.lines + right.lines;
+        if total == 0 {
+            (0.0, together.lines)
+        } else {
+            ((together.lines as f64) / (total as f64), together.lines)
         }
+    }
 
-        new
+    /// How many grouped deltas each file appears in
+    pub fn change_counts(&self) -> impl Iterator<Item = (&ChangedFile, usize)> {
+        self.file_tallies
+            .iter()
+            .map(|(file, tally)| (self.files.file(*file), tally.deltas))
+    }
+
+    fn file_tally(&self, file: FileId) -> Tally {
+        self.file_tallies.get(&file).copied().unwrap_or_default()
     }
 
-    fn deltas_containing(
-        &self,
-        item: &ChangedFile,
-        other_file: &ChangedFile,
-    ) -> (Key, usize, usize) {
+    /// The tallies of each file in the pair and of the pair together
+    fn tallies(&self, key: &Key) -> (Tally, Tally, Tally) {
+        let (left, right) = (self.files.id(key.left()), self.files.id(key.right()));
+        let tally = |file: Option<FileId>| file.map(|file| self.file_tally(file));
+        let together = left
+            .zip(right)
+            .and_then(|(left, right)| self.pair_tallies.get(&(left.min(right), left.max(right))))
+            .copied();
         (
-            Key::new(item.clone(), other_file.clone()),
-            self.deltas_containing_both(item, other_file),
-            self.deltas_containing_either(item, other_file),
+            tally(left).unwrap_or_default(),
+            tally(right).unwrap_or_default(),
+            together.unwrap_or_default(),
         )
     }
+}
 
-    fn deltas_containing_both(&self, item: &ChangedFile, other_file: &ChangedFile) -> usize {
-        self.change_to_delta
-            .get(item)
-            .zip(self.change_to_delta.get(other_file))
-            .map_or(0, |(left, right)| left.intersection(right).count())
+/// Take back what something was counted with in each group it was part of,
+/// and count it once with what it has in the group they joined into
+fn recount<K: Copy + Eq + std::hash::Hash>(
+    tallies: &mut HashMap<K, Tally>,
+    key: K,
+    before: [Option<usize>; 2],
+    lines: usize,
+) {
+    let tally = tallies.entry(key).or_default();
+    for lines in before.into_iter().flatten() {
+        tally.deltas -= 1;
+        tally.lines -= lines;
     }
+    tally.deltas += 1;
+    tally.lines += lines;
+}
 
-    fn deltas_containing_either(&self, item: &ChangedFile, other_file: &ChangedFile) -> usize {
-        self.change_to_delta
-            .get(item)
-            .zip(self.change_to_delta.get(other_file))
-            .map_or(0, |(left, right)| left.union(right).count())
+/// Add up the changes of two grouped deltas, combining any file in both
+fn combine(mut changes: Changes, more: Changes) -> Changes {
+    for (file, change) in more {
+        let combined = changes
+            .get(&file)
+            .map_or(change, |existing| existing.combine(change));
+        changes.insert(file, combined);
     }
+    changes
 }
 
 impl Display for CouplingResult {
@@ -179,26 +489,30 @@ impl Display for CouplingResult {
         let mut coupling: Vec<_> = self.result.clone();
         coupling.sort_by(display_order);
 
+        let mut header = vec!["File A", "File B", "Together %", "Together", "Commits"];
+        if self.churn.is_some() {
+            header.extend(["Churn %", "Lines together"]);
+        }
+
         let mut table = Table::new();
         table
             .load_preset(UTF8_FULL)
             .apply_modifier(UTF8_ROUND_CORNERS)
             .set_content_arrangement(ContentArrangement::Dynamic)
-            .set_header(vec![
-                "File A",
-                "File B",
-                "Together %",
-                "Together",
-                "Commits",
-            ]);
+            .set_header(header);
         for (key, (strength, together, total)) in coupling {
-            table.add_row(vec![
+            let mut row = vec![
                 key.left.clone().into(),
                 key.right.clone().into(),
                 format!("{:.2}%", strength * 100.0),
                 format!("{together}"),
                 format!("{total}"),
-            ]);
+            ];
+            if let Some((churn, lines)) = self.churn.as_ref().and_then(|churn| churn.get(&key)) {
+                row.push(format!("{:.2}%", churn * 100.0));
+                row.push(format!("{lines}"));
+            }
+            table.add_row(row);
         }
 
         writeln!(formatter, "{table}")
@@ -207,13 +521,26 @@ impl Display for CouplingResult {
 
 #[cfg(test)]
 mod tests {
+    use regex::Regex;
     use time::OffsetDateTime;
 
     use crate::{
-        model::delta::Delta,
+        model::{author::Author, change::Change, commit::Commit, delta::Delta},
         statistics::{Key, Statistics, Strategy},
     };
 
+    /// A commit `minutes` after the same moment
+    fn commit(hash: &str, minutes: i64, author: &str, message: &str) -> Commit {
+        Commit::new(
+            hash.into(),
+            vec![],
+            OffsetDateTime::from_unix_timestamp(1_700_000_000).unwrap()
+                + time::Duration::minutes(minutes),
+            Author::new(author, &format!("{author}@example.com")),
+            message.into(),
+        )
+    }
+
     #[allow(clippy::semicolon_if_nothing_returned)]
     #[tokio::test]
     async fn adding_one_file_to_statistics_will_give_a_count_of_zero() {
@@ -334,6 +661,80 @@ mod tests {
         );
     }
 
+    #[test]
+    fn files_that_never_changed_together_are_not_paired() {
+        let now = OffsetDateTime::now_utc();
+        let actual = [
+            Delta::new("1".into(), now, vec!["file_1".into(), "file_2".into()]),
+            Delta::new("2".into(), now, vec!["file_2".into()]),
+            Delta::new("3".into(), now, vec!["file_3".into()]),
+        ]
+        .iter()
+        .fold(Statistics::default(), |statistics, delta| {
+            statistics.add_delta(delta, &Strategy::Hash)
+        });
+
+        assert_eq!(
+            actual.coupling().result,
+            vec![(Key::new("file_1".into(), "file_2".into()), (0.5, 1, 2))]
+        );
+    }
+
+    #[test]
+    fn churn_weighs_each_delta_by_the_lines_it_changed() {
+        let statistics = [
+            Delta::for_commit(
+                &commit("1", 0, "alex", ""),
+                vec![
+                    ("file_1".into(), Change::new(100, 50)),
+                    ("file_2".into(), Change::new(40, 10)),
+                ],
+            ),
+            Delta::for_commit(
+                &commit("2", 0, "alex", ""),
+                vec![("file_1".into(), Change::new(1, 1))],
+            ),
+            Delta::for_commit(
+                &commit("3", 0, "alex", ""),
+                vec![("file_2".into(), Change::new(0, 2))],
+            ),
+        ]
+        .iter()
+        .fold(Statistics::default(), |statistics, delta| {
+            statistics.add_delta(delta, &Strategy::Hash)
+        });
+        let key = Key::new("file_1".into(), "file_2".into());
+
+        assert_eq!(statistics.calculation(&key), (1.0 / 3.0, 1, 3));
+        assert_eq!(statistics.churn(&key), (200.0 / 204.0, 200));
+    }
+
+    #[test]
+    fn grouping_adds_up_the_churn_of_files_in_both() {
+        let statistics = [
+            Delta::for_commit(
+                &commit("1", 0, "alex", ""),
+                vec![
+                    ("file_1".into(), Change::new(1, 2)),
+                    ("file_2".into(), Change::new(3, 0)),
+                ],
+            ),
+            Delta::for_commit(
+                &commit("2", 0, "alex", ""),
+                vec![("file_1".into(), Change::new(10, 20))],
+            ),
+        ]
+        .iter()
+        .fold(Statistics::default(), |statistics, delta| {
+            statistics.add_delta(delta, &Strategy::CommitTime(time::Duration::minutes(5)))
+        });
+
+        assert_eq!(
+            statistics.churn(&Key::new("file_1".into(), "file_2".into())),
+            (1.0, 36)
+        );
+    }
+
     #[allow(clippy::semicolon_if_nothing_returned)]
     #[tokio::test]
     async fn commit_time_strategy_groups_files_in_same_window() {
@@ -356,6 +757,274 @@ mod tests {
         );
     }
 
+    #[allow(clippy::semicolon_if_nothing_returned)]
+    #[tokio::test]
+    async fn author_time_strategy_keeps_different_people_apart() {
+        let strategy = Strategy::AuthorTime(time::Duration::minutes(60));
+        let statistics = Statistics::default()
+            .add_delta(
+                &Delta::for_commit(
+                    &commit("1", 0, "alex", ""),
+                    vec![("file_a".into(), Change::default())],
+                ),
+                &strategy,
+            )
+            .add_delta(
+                &Delta::for_commit(
+                    &commit("2", 0, "alex", ""),
+                    vec![("file_b".into(), Change::default())],
+                ),
+                &strategy,
+            )
+            .add_delta(
+                &Delta::for_commit(
+                    &commit("3", 0, "sam", ""),
+                    vec![("file_c".into(), Change::default())],
+                ),
+                &strategy,
+            );
+        assert_eq!(
+            statistics.coupling().result,
+            vec![(Key::new("file_a".into(), "file_b".into()), (1.0, 1, 1))]
+        );
+    }
+
+    #[test]
+    fn author_time_strategy_groups_only_the_same_set_of_authors() {
+        let strategy = Strategy::AuthorTime(time::Duration::minutes(60));
+        let statistics = [
+            commit("1", 0, "alex", ""),
+            commit("2", 0, "alex", "Co-authored-by: sam <sam@example.com>\n"),
+            commit("3", 0, "sam", "Co-authored-by: alex <alex@example.com>\n"),
+        ]
+        .iter()
+        .zip(["file_a", "file_b", "file_c"])
+        .fold(Statistics::default(), |statistics, (commit, file)| {
+            statistics.add_delta(
+                &Delta::for_commit(commit, vec![(file.into(), Change::default())]),
+                &strategy,
+            )
+        });
+
+        assert_eq!(
+            statistics.coupling().result,
+            vec![(Key::new("file_b".into(), "file_c".into()), (1.0, 1, 1))]
+        );
+    }
+
+    #[allow(clippy::semicolon_if_nothing_returned)]
+    #[tokio::test]
+    async fn ticket_strategy_groups_commits_for_the_same_work_item() {
+        let strategy = Strategy::Ticket(Regex::new("[A-Z]+-[0-9]+").unwrap());
+        let statistics = Statistics::default()
+            .add_delta(
+                &Delta::for_commit(
+                    &commit("1", 0, "alex", "PROJ-1 start"),
+                    vec![("file_a".into(), Change::default())],
+                ),
+                &strategy,
+            )
+            .add_delta(
+                &Delta::for_commit(
+                    &commit("2", 0, "alex", "PROJ-2 other"),
+                    vec![("file_b".into(), Change::default())],
+                ),
+                &strategy,
+            )
+            .add_delta(
+                &Delta::for_commit(
+                    &commit("3", 0, "alex", "finish PROJ-1"),
+                    vec![("file_c".into(), Change::default())],
+                ),
+                &strategy,
+            );
+        assert_eq!(
+            statistics.coupling().result,
+            vec![(Key::new("file_a".into(), "file_c".into()), (1.0, 1, 1))]
+        );
+    }
+
+    #[allow(clippy::semicolon_if_nothing_returned)]
+    #[tokio::test]
+    async fn ticket_strategy_keeps_untagged_commits_separate() {
+        let strategy = Strategy::Ticket(Regex::new(r"\[(\w+-\d+)\]").unwrap());
+        let statistics = Statistics::default()
+            .add_delta(
+                &Delta::for_commit(
+                    &commit("1", 0, "alex", "tidy up"),
+                    vec![("file_a".into(), Change::default())],
+                ),
+                &strategy,
+            )
+            .add_delta(
+                &Delta::for_commit(
+                    &commit("2", 0, "alex", "tidy up"),
+                    vec![("file_b".into(), Change::default())],
+                ),
+                &strategy,
+            );
+        assert_eq!(statistics.coupling().result, vec![]);
+    }
+
+    #[allow(clippy::semicolon_if_nothing_returned)]
+    #[tokio::test]
+    async fn pull_request_strategy_groups_commits_by_their_merge() {
+        let now = OffsetDateTime::now_utc();
+        let statistics = Statistics::default()
+            .add_delta(
+                &Delta::new("1".into(), now, vec!["file_a".into()]).in_pull_request("3".into()),
+                &Strategy::PullRequest,
+            )
+            .add_delta(
+                &Delta::new("2".into(), now, vec!["file_b".into()]).in_pull_request("3".into()),
+                &Strategy::PullRequest,
+            )
+            .add_delta(
+                &Delta::new("3".into(), now, vec!["file_c".into()]),
+                &Strategy::PullRequest,
+            )
+            .add_delta(
+                &Delta::new("4".into(), now, vec!["file_a".into()]),
+                &Strategy::PullRequest,
+            );
+        assert_eq!(
+            statistics.coupling().result,
+            vec![
+                (Key::new("file_a".into(), "file_b".into()), (0.5, 1, 2)),
+                (Key::new("file_a".into(), "file_c".into()), (0.5, 1, 2)),
+                (Key::new("file_b".into(), "file_c".into()), (1.0, 1, 1)),
+            ]
+        );
+    }
+
+    #[allow(clippy::semicolon_if_nothing_returned)]
+    #[tokio::test]
+    async fn sliding_window_groups_commits_across_a_bucket_boundary() {
+        // These are a minute apart, but either side of an hour boundary, so
+        // CommitTime would put them in different buckets.
+        let boundary = OffsetDateTime::from_unix_timestamp(1_699_999_200).unwrap();
+        let strategy = Strategy::SlidingWindow(time::Duration::minutes(5));
+        let statistics = Statistics::default()
+            .add_delta(
+                &Delta::new(
+                    "1".into(),
+                    boundary - time::Duration::seconds(30),
+                    vec!["file_a".into()],
+                ),
+                &strategy,
+            )
+            .add_delta(
+                &Delta::new(
+                    "2".into(),
+                    boundary + time::Duration::seconds(30),
+                    vec!["file_b".into()],
+                ),
+                &strategy,
+            );
+        assert_eq!(
+            statistics.coupling().result,
+            vec![(Key::new("file_a".into(), "file_b".into()), (1.0, 1, 1))]
+        );
+    }
+
+    #[allow(clippy::semicolon_if_nothing_returned)]
+    #[tokio::test]
+    async fn sliding_window_keeps_distant_commits_apart() {
+        let base = OffsetDateTime::from_unix_timestamp(1_700_000_000).unwrap();
+        let strategy = Strategy::SlidingWindow(time::Duration::minutes(5));
+        let statistics = Statistics::default()
+            .add_delta(
+                &Delta::new("1".into(), base, vec!["file_a".into(), "file_b".into()]),
+                &strategy,
+            )
+            .add_delta(
+                &Delta::new(
+                    "2".into(),
+                    base + time::Duration::minutes(6),
+                    vec!["file_b".into(), "file_c".into()],
+                ),
+                &strategy,
+            );
+        assert_eq!(
+            statistics.coupling().result,
+            vec![
+                (Key::new("file_a".into(), "file_b".into()), (0.5, 1, 2)),
+                (Key::new("file_b".into(), "file_c".into()), (0.5, 1, 2)),
+            ]
+        );
+    }
+
+    #[allow(clippy::semicolon_if_nothing_returned)]
+    #[tokio::test]
+    async fn sliding_window_merges_sessions_bridged_by_a_later_delta() {
+        // Deltas arrive newest first from the repository, and a delta that
+        // lands between two sessions joins them into one.
+        let base = OffsetDateTime::from_unix_timestamp(1_700_000_000).unwrap();
+        let strategy = Strategy::SlidingWindow(time::Duration::minutes(5));
+        let statistics = Statistics::default()
+            .add_delta(
+                &Delta::new(
+                    "1".into(),
+                    base + time::Duration::minutes(8),
+                    vec!["file_a".into()],
+                ),
+                &strategy,
+            )
+            .add_delta(
+                &Delta::new("2".into(), base, vec!["file_b".into()]),
+                &strategy,
+            )
+            .add_delta(
+                &Delta::new(
+                    "3".into(),
+                    base + time::Duration::minutes(4),
+                    vec!["file_c".into()],
+                ),
+                &strategy,
+            );
+        assert_eq!(
+            statistics.coupling().result,
+            vec![
+                (Key::new("file_a".into(), "file_b".into()), (1.0, 1, 1)),
+                (Key::new("file_a".into(), "file_c".into()), (1.0, 1, 1)),
+                (Key::new("file_b".into(), "file_c".into()), (1.0, 1, 1)),
+            ]
+        );
+    }
+
+    #[test]
+    fn bridged_sessions_count_the_files_and_pairs_in_both_once() {
+        let statistics = [
+            Delta::for_commit(
+                &commit("1", 0, "alex", ""),
+                vec![
+                    ("file_a".into(), Change::new(1, 0)),
+                    ("file_b".into(), Change::new(2, 0)),
+                ],
+            ),
+            Delta::for_commit(
+                &commit("2", 8, "alex", ""),
+                vec![
+                    ("file_a".into(), Change::new(10, 0)),
+                    ("file_c".into(), Change::new(20, 0)),
+                ],
+            ),
+            Delta::for_commit(
+                &commit("3", 4, "alex", ""),
+                vec![("file_b".into(), Change::new(100, 0))],
+            ),
+        ]
+        .iter()
+        .fold(Statistics::default(), |statistics, delta| {
+            statistics.add_delta(delta, &Strategy::SlidingWindow(time::Duration::minutes(5)))
+        });
+
+        let pair = Key::new("file_a".into(), "file_b".into());
+        assert_eq!(statistics.calculation(&pair), (1.0, 1, 1));
+        assert_eq!(statistics.churn(&pair), (1.0, 113));
+        assert!(statistics.change_counts().all(|(_, count)| count == 1));
+    }
+
     #[allow(clippy::semicolon_if_nothing_returned)]
     #[tokio::test]
     async fn statistics_render_pretty() {
@@ -0,0 +1,350 @@
+use std::{
+    collections::BTreeMap,
+    fmt::{Display, Formatter},
+    io::Write,
+};
+
+use comfy_table::{ContentArrangement, Table, modifiers::UTF8_ROUND_CORNERS, presets::UTF8_FULL};
+use serde::Serialize;
+use time::{Date, Duration, Month, OffsetDateTime, UtcOffset};
+
+use crate::{
+    model::delta::Delta,
+    statistics::{Calculation, Statistics, Strategy},
+};
+
+/// How much history each step of a trend covers, in UTC
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum Period {
+    /// ISO weeks, starting on Monday
+    Weekly,
+    /// Calendar months
+    Monthly,
+    /// Calendar quarters, starting in January
+    Quarterly,
+}
+
+impl Period {
+    fn start(self, timestamp: OffsetDateTime) -> Date {
+        let date = timestamp.to_offset(UtcOffset::UTC).date();
+        match self {
+            Self::Weekly => date - Duration::days(date.weekday().number_days_from_monday().into()),
+            Self::Monthly => date.replace_day(1).expect("Every month has a first day"),
+            Self::Quarterly => Month::try_from((u8::from(date.month()) - 1) / 3 * 3 + 1)
+                .and_then(|month| Date::from_calendar_date(date.year(), month, 1))
+                .expect("Every quarter starts on the first day of a month"),
+        }
+    }
+
+    fn label(self, start: Date) -> String {
+        match self {
+            Self::Weekly => {
+                let (year, week, _) = start.to_iso_week_date();
+                format!("{year}-W{week:02}")
+            }
+            Self::Monthly => format!("{}-{:02}", start.year(), u8::from(start.month())),
+            Self::Quarterly => format!(
+                "{}-Q{}",
+                start.year(),
+                (u8::from(start.month()) - 1) / 3 + 1
+            ),
+        }
+    }
+}
+
+/// The coupling of a pair within one period
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Point {
+    period: String,
+    file_a: String,
+    file_b: String,
+    score: f64,
+    together: usize,
+    commits: usize,
+}
+
+impl Point {
+    /// The period, such as `2024-W07`, `2024-02` or `2024-Q1`
+    #[must_use]
+    pub fn period(&self) -> &str {
+        &self.period
+    }
+
+    /// The pair's files, in the order of a [`Key`](crate::Key)
+    #[must_use]
+    pub fn files(&self) -> (&str, &str) {
+        (&self.file_a, &self.file_b)
+    }
+
+    /// The pair's coupling within the period
+    #[must_use]
+    pub const fn calculation(&self) -> Calculation {
+        (self.score, self.together, self.commits)
+    }
+}
+
+/// How the coupling of the most strongly coupled pairs changed over time
+#[derive(Debug)]
+pub struct Trend {
+    points: Vec<Point>,
+}
+
+impl Trend {
+    /// Follow the `top` most strongly coupled pairs across all the deltas
+    /// through each period that has deltas
+    #[must_use]
+    pub fn new(deltas: &[Delta], strategy: &Strategy, period: Period, top: usize) -> Self {
+        let pairs = deltas
+            .iter()
+            .fold(Statistics::default(), |statistics, delta| {
+                statistics.add_delta(delta, strategy)
+            })
+            .coupling()
+            .strongest(top);
+
+        let mut periods: BTreeMap<Date, Statistics> = BTreeMap::new();
+        for delta in deltas {
+            let start = period.start(delta.timestamp());
+            let statistics = periods.remove(&start).unwrap_or_default();
+            periods.insert(start, statistics.add_delta(delta, strategy));
+        }
+
+        Self {
+            points: pairs
+                .iter()
+                .flat_map(|key| {
+                    periods.iter().map(|(start, statistics)| {
+                        let (score, together, commits) = statistics.calculation(key);
+                        Point {
+                            period: period.label(*start),
+                            file_a: key.left().clone().into(),
+                            file_b: key.right().clone().into(),
+                            score,
+                            together,
+                            commits,
+                        }
+                    })
+                })
+                .collect(),
+        }
+    }
+
+    /// Whether no pair changed together
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+
+    /// Each pair's point in each period, pair by pair
+    pub fn iter(&self) -> std::slice::Iter<'_, Point> {
+        self.points.iter()
+    }
+
+    /// Write one row per pair and period, with a header
+    ///
+    /// # Errors
+    ///
+    /// When the writer fails
+    pub fn write_csv<W: Write>(&self, writer: W) -> Result<(), csv::Error> {
+        let mut writer = csv::Writer::from_writer(writer);
+        for point in &self.points {
+            writer.serialize(point)?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Write the points as a JSON array
+    ///
+    /// # Errors
+    ///
+    /// When the writer fails
+    pub fn write_json<W: Write>(&self, writer: W) -> Result<(), serde_json::Error> {
+        serde_json::to_writer_pretty(writer, &self.points)
+    }
+}
+
+impl<'a> IntoIterator for &'a Trend {
+    type IntoIter = std::slice::Iter<'a, Point>;
+    type Item = &'a Point;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.points.iter()
+    }
+}
+
+impl Display for Trend {
+    fn fmt(&self, formatter: &mut Formatter<'_>) -> std::fmt::Result {
+        let mut table = Table::new();
+        table
+            .load_preset(UTF8_FULL)
+            .apply_modifier(UTF8_ROUND_CORNERS)
+            .set_content_arrangement(ContentArrangement::Dynamic)
+            .set_header(vec![
+                "Period",
+                "File A",
+                "File B",
+                "Together %",
+                "Together",
+                "Commits",
+            ]);
+        for point in &self.points {
+            table.add_row(vec![
+                point.period.clone(),
+                point.file_a.clone(),
+                point.file_b.clone(),
+                format!("{:.2}%", point.score * 100.0),
+                format!("{}", point.together),
+                format!("{}", point.commits),
+            ]);
+        }
+
+        writeln!(formatter, "{table}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use time::{Date, Month, OffsetDateTime};
+
+    use crate::{
+        model::delta::Delta,
+        statistics::Strategy,
+        trend::{Period, Point, Trend},
+    };
+
+    fn at(year: i32, month: Month, day: u8) -> OffsetDateTime {
+        Date::from_calendar_date(year, month, day)
+            .unwrap()
+            .midnight()
+            .assume_utc()
+    }
+
+    #[test]
+    fn periods_start_on_their_calendar_boundaries() {
+        let timestamp = at(2024, Month::August, 15);
+
+        assert_eq!(
+            Period::Weekly.start(timestamp),
+            Date::from_calendar_date(2024, Month::August, 12).unwrap()
+        );
+        assert_eq!(
+            Period::Monthly.start(timestamp),
+            Date::from_calendar_date(2024, Month::August, 1).unwrap()
+        );
+        assert_eq!(
+            Period::Quarterly.start(timestamp),
+            Date::from_calendar_date(2024, Month::July, 1).unwrap()
+        );
+    }
+
+    #[test]
+    fn periods_are_labelled_for_humans() {
+        let timestamp = at(2024, Month::August, 15);
+
+        assert_eq!(
+            Period::Weekly.label(Period::Weekly.start(timestamp)),
+            "2024-W33"
+        );
+        assert_eq!(
+            Period::Monthly.label(Period::Monthly.start(timestamp)),
+            "2024-08"
+        );
+        assert_eq!(
+            Period::Quarterly.label(Period::Quarterly.start(timestamp)),
+            "2024-Q3"
+        );
+    }
+
+    #[test]
+    fn each_period_is_scored_separately() {
+        let trend = Trend::new(
+            &[
+                Delta::new(
+                    "1".into(),
+                    at(2024, Month::January, 3),
+                    vec!["file_1".into(), "file_2".into()],
+                ),
+                Delta::new(
+                    "2".into(),
+                    at(2024, Month::February, 3),
+                    vec!["file_1".into(), "file_2".into()],
+                ),
+                Delta::new(
+                    "3".into(),
+                    at(2024, Month::February, 4),
+                    vec!["file_1".into()],
+                ),
+            ],
+            &Strategy::Hash,
+            Period::Monthly,
+            10,
+        );
+
+        assert_eq!(
+            trend.points,
+            vec![
+                Point {
+                    period: "2024-01".into(),
+                    file_a: "file_1".into(),
+                    file_b: "file_2".into(),
+                    score: 1.0,
+                    together: 1,
+                    commits: 1,
+                },
+                Point {
+                    period: "2024-02".into(),
+                    file_a: "file_1".into(),
+                    file_b: "file_2".into(),
+                    score: 0.5,
+                    together: 1,
+                    commits: 2,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn a_period_without_either_file_scores_zero() {
+        let trend = Trend::new(
+            &[
+                Delta::new(
+                    "1".into(),
+                    at(2024, Month::January, 3),
+                    vec!["file_1".into(), "file_2".into()],
+                ),
+                Delta::new("2".into(), at(2024, Month::March, 3), vec!["file_3".into()]),
+            ],
+            &Strategy::Hash,
+            Period::Monthly,
+            10,
+        );
+
+        let march = trend.points.last().unwrap();
+        assert_eq!(march.period, "2024-03");
+        assert_eq!((march.score, march.together, march.commits), (0.0, 0, 0));
+    }
+
+    #[test]
+    fn trend_can_be_written_as_csv() {
+        let trend = Trend::new(
+            &[Delta::new(
+                "1".into(),
+                at(2024, Month::January, 3),
+                vec!["file_1".into(), "file_2".into()],
+            )],
+            &Strategy::Hash,
+            Period::Monthly,
+            10,
+        );
+        let mut output = vec![];
+
+        trend.write_csv(&mut output).unwrap();
+
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "period,file_a,file_b,score,together,commits\n2024-01,file_1,file_2,1.0,1,1\n"
+        );
+    }
+}